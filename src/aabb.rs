@@ -0,0 +1,96 @@
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use std::f32;
+use std::mem;
+
+// Padding for the boxes of flat shapes, which would otherwise have no thickness along their
+// normal when it is axis-aligned, and be missed by the slab test.
+pub(crate) const FLAT_PADDING: f32 = 1e-4;
+
+// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    // An empty box, suitable as the starting point of a union.
+    pub fn empty() -> Self {
+        Aabb::new(Vec3::from(f32::MAX), Vec3::from(f32::MIN))
+    }
+
+    // Slab test (Andrew Kensler's formulation).
+    pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction;
+            if inverse_direction < 0.0 {
+                mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let d = self.extent();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let d = self.extent();
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    // The box grown by `amount` on every side.
+    pub fn padded(self, amount: f32) -> Self {
+        let padding = Vec3::from(amount);
+        Aabb::new(self.min - padding, self.max + padding)
+    }
+
+    pub fn grow(&self, point: Vec3) -> Self {
+        Aabb::new(
+            Vec3::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            Vec3::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        )
+    }
+}
+
+pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+    box0.grow(box1.min).grow(box1.max)
+}
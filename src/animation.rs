@@ -0,0 +1,229 @@
+use crate::aabb::{self, Aabb};
+use crate::hittable::{HitResult, Hittable};
+use crate::quaternion::Quaternion;
+use crate::ray::Ray;
+use crate::transform::{self, Transform};
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+// Samples taken between keyframes when bounding the swept volume of an animation.
+const BOUNDING_STEPS: usize = 32;
+
+// Pose of an animated object at `time`: scaled, then rotated, then translated.
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    // The object as it is, at `time`.
+    pub fn at(time: f32) -> Self {
+        Keyframe {
+            time,
+            translation: Vec3::default(),
+            rotation: Quaternion::identity(),
+            scale: Vec3::from(1.0),
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quaternion) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::translate(self.translation)
+            * self.rotation.to_transform()
+            * Transform::scale(self.scale)
+    }
+}
+
+// Transform moving between keyframes: translation and scale are interpolated linearly and
+// rotation by slerp, so rotations more than half a turn apart go the short way round and need
+// keyframes in between. The object holds still before the first keyframe and after the last.
+#[derive(Clone, Debug)]
+pub struct AnimatedTransform {
+    keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    // Keyframes are sorted by time. Panics if there are none, or if a scale factor is not
+    // positive.
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "Cannot animate without keyframes.");
+        assert!(
+            keyframes
+                .iter()
+                .all(|k| k.scale.x > 0.0 && k.scale.y > 0.0 && k.scale.z > 0.0),
+            "Animated scale factors must be positive."
+        );
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        AnimatedTransform { keyframes }
+    }
+
+    pub fn at(&self, time: f32) -> Transform {
+        match self.segment(time) {
+            Some((k0, k1)) => {
+                let s = (time - k0.time) / (k1.time - k0.time);
+                Keyframe {
+                    time,
+                    translation: k0.translation + s * (k1.translation - k0.translation),
+                    rotation: k0.rotation.slerp(&k1.rotation, s),
+                    scale: k0.scale + s * (k1.scale - k0.scale),
+                }
+                .transform()
+            }
+            None if time < self.keyframes[0].time => self.keyframes[0].transform(),
+            None => self.keyframes[self.keyframes.len() - 1].transform(),
+        }
+    }
+
+    // Box enclosing `bounds` wherever the animation takes it over [time0, time1].
+    //
+    // Poses are sampled between keyframes and the boxes at each are padded by how far any
+    // point of `bounds` can move between samples, which is bounded since every part of the
+    // motion has a constant speed between two keyframes.
+    pub fn bounding_box(&self, bounds: &Aabb, time0: f32, time1: f32) -> Aabb {
+        let mut times = vec![time0];
+        times.extend(
+            self.keyframes
+                .iter()
+                .map(|k| k.time)
+                .filter(|&t| t > time0 && t < time1),
+        );
+        times.push(time1);
+
+        // Distance from the object's origin to the furthest point of `bounds`.
+        let furthest = |i: usize| bounds.min[i].abs().max(bounds.max[i].abs());
+        let radius = Vec3::new(furthest(0), furthest(1), furthest(2)).length();
+
+        let mut result = self.at(time0).bounding_box(bounds);
+        for pair in times.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if end <= start {
+                continue;
+            }
+            let step = (end - start) / BOUNDING_STEPS as f32;
+            let padding = Vec3::from(0.5 * step * self.speed(0.5 * (start + end), radius));
+            for i in 0..=BOUNDING_STEPS {
+                let sample = self.at(start + i as f32 * step).bounding_box(bounds);
+                let swept = Aabb::new(sample.min - padding, sample.max + padding);
+                result = aabb::surrounding_box(&result, &swept);
+            }
+        }
+        result
+    }
+
+    // Factor by which the animation scales every length, if it is the same along every axis
+    // and at every time.
+    pub fn uniform_scale(&self) -> Option<f32> {
+        let scale = self.keyframes[0].scale.x;
+        let uniform = self
+            .keyframes
+            .iter()
+            .all(|k| (0..3).all(|i| (k.scale[i] - scale).abs() <= 1e-6 * scale));
+        if uniform {
+            Some(scale)
+        } else {
+            None
+        }
+    }
+
+    // Keyframes on either side of `time`, if it is within the animation.
+    fn segment(&self, time: f32) -> Option<(&Keyframe, &Keyframe)> {
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        if next == 0 || next == self.keyframes.len() {
+            return None;
+        }
+        Some((&self.keyframes[next - 1], &self.keyframes[next]))
+    }
+
+    // Bound on the speed at `time` of any point within `radius` of the object's origin.
+    fn speed(&self, time: f32, radius: f32) -> f32 {
+        let (k0, k1) = match self.segment(time) {
+            Some(segment) => segment,
+            None => return 0.0,
+        };
+        let duration = k1.time - k0.time;
+        let largest = |v: Vec3| v.x.abs().max(v.y.abs()).max(v.z.abs());
+        let translation = (k1.translation - k0.translation).length() / duration;
+        let scaling = largest(k1.scale - k0.scale) / duration;
+        let turning = k0.rotation.angle_to(&k1.rotation) / duration;
+        let scale = largest(k0.scale).max(largest(k1.scale));
+        translation + (turning * scale + scaling) * radius
+    }
+}
+
+// Object moved by an animated transform, following the time of each ray, for motion blur. Like
+// `Transformed`, instances share the object.
+pub struct Animated<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    animation: AnimatedTransform,
+    uniform_scale: Option<f32>,
+}
+
+impl<H: Hittable + ?Sized> Animated<H> {
+    pub fn new(object: Arc<H>, animation: AnimatedTransform) -> Self {
+        Animated {
+            object,
+            uniform_scale: animation.uniform_scale(),
+            animation,
+        }
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Animated<H> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let transform = self.animation.at(ray.time);
+        let mut hit =
+            transform::hit_transformed(self.object.as_ref(), &transform, ray, t_min, t_max)?;
+        hit.sampleable &= self.uniform_scale.is_some();
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let bounds = self.object.bounding_box(time0, time1)?;
+        Some(self.animation.bounding_box(&bounds, time0, time1))
+    }
+
+    // Only known when the scale stays uniform and constant, so that the area doesn't change.
+    fn area(&self) -> f32 {
+        match self.uniform_scale {
+            Some(scale) => scale * scale * self.object.area(),
+            None => 0.0,
+        }
+    }
+
+    fn sample_surface(&self, u: (f32, f32), time: f32) -> Option<(Vec3, Vec3)> {
+        let (point, normal) = self.object.sample_surface(u, time)?;
+        let transform = self.animation.at(time);
+        Some((
+            transform.point(point),
+            Vec3::unit_from(transform.normal(normal)),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        self.object
+            .emitters()
+            .into_iter()
+            .map(|emitter| {
+                Arc::new(Animated::new(emitter, self.animation.clone())) as Arc<dyn Hittable>
+            })
+            .collect()
+    }
+}
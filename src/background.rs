@@ -0,0 +1,61 @@
+use crate::environment::EnvironmentLight;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Radiance coming from infinitely far away, seen by rays that hit nothing.
+pub enum Background {
+    Color(Vec3),
+    // Blends from `bottom` when looking straight down to `top` when looking straight up.
+    Gradient { bottom: Vec3, top: Vec3 },
+    // Equirectangular map: u goes around the vertical axis, with the center of the image
+    // towards -Z, and v from straight down (0) to straight up (1). The texture is also given
+    // the unit direction as point, so procedural textures can be used as skies.
+    Environment(Arc<dyn Texture>),
+    // Image based lighting, which can also be sampled like a light.
+    EnvironmentLight(EnvironmentLight),
+}
+
+impl Background {
+    pub fn color(&self, direction: Vec3) -> Vec3 {
+        let direction = Vec3::unit_from(direction);
+        match self {
+            Background::Color(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.y + 1.0);
+                (1.0 - t) * *bottom + t * *top
+            }
+            Background::Environment(texture) => {
+                let (u, v) = equirectangular_uv(direction);
+                texture.value(u, v, &direction)
+            }
+            Background::EnvironmentLight(light) => light.radiance(direction),
+        }
+    }
+
+    // The part of the background that can be sampled as a light.
+    pub fn light(&self) -> Option<&EnvironmentLight> {
+        match self {
+            Background::EnvironmentLight(light) => Some(light),
+            _ => None,
+        }
+    }
+}
+
+// Light blue sky, deeper towards the zenith.
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Vec3::new(0.75, 0.85, 1.0),
+            top: Vec3::new(0.25, 0.55, 1.0),
+        }
+    }
+}
+
+fn equirectangular_uv(direction: Vec3) -> (f32, f32) {
+    let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+    let v = 1.0 - direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
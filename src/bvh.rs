@@ -0,0 +1,187 @@
+use crate::aabb::{self, Aabb};
+use crate::hittable::{HitResult, Hittable};
+use crate::ray::Ray;
+
+use std::sync::Arc;
+
+// Number of centroid buckets evaluated per axis by the surface area heuristic.
+const SAH_BUCKETS: usize = 12;
+// Relative cost of traversing a node versus intersecting one primitive.
+const TRAVERSAL_COST: f32 = 0.125;
+const MAX_PRIMITIVES_IN_LEAF: usize = 4;
+
+enum BvhChildren {
+    Leaf(Vec<Box<dyn Hittable>>),
+    Branch(Box<BvhNode>, Box<BvhNode>),
+}
+
+// Bounding volume hierarchy over a set of bounded hittables, split with the surface area heuristic.
+pub struct BvhNode {
+    bounding_box: Aabb,
+    children: BvhChildren,
+}
+
+impl BvhNode {
+    // Panics if any of the hittables is unbounded (see World::build_bvh to mix both kinds).
+    pub fn new(hittables: Vec<Box<dyn Hittable>>, time0: f32, time1: f32) -> Self {
+        let primitives = hittables
+            .into_iter()
+            .map(|hittable| {
+                let bounding_box = hittable
+                    .bounding_box(time0, time1)
+                    .expect("BVH cannot hold unbounded hittables.");
+                (bounding_box, hittable)
+            })
+            .collect();
+        BvhNode::build(primitives)
+    }
+
+    fn build(mut primitives: Vec<(Aabb, Box<dyn Hittable>)>) -> Self {
+        let bounding_box = primitives
+            .iter()
+            .fold(Aabb::empty(), |acc, (b, _)| aabb::surrounding_box(&acc, b));
+
+        let n = primitives.len();
+        if n <= 1 {
+            return BvhNode::leaf(bounding_box, primitives);
+        }
+
+        let centroid_bounds = primitives
+            .iter()
+            .fold(Aabb::empty(), |acc, (b, _)| acc.grow(b.centroid()));
+
+        // Find the cheapest bucket boundary over all three axes.
+        let mut best: Option<(usize, usize, f32)> = None;
+        for axis in 0..3 {
+            let low = centroid_bounds.min[axis];
+            let extent = centroid_bounds.max[axis] - low;
+            if extent <= 0.0 {
+                continue;
+            }
+
+            let mut counts = [0usize; SAH_BUCKETS];
+            let mut bounds = [Aabb::empty(); SAH_BUCKETS];
+            for (b, _) in primitives.iter() {
+                let i = bucket_index(b.centroid()[axis], low, extent);
+                counts[i] += 1;
+                bounds[i] = aabb::surrounding_box(&bounds[i], b);
+            }
+
+            for split in 1..SAH_BUCKETS {
+                let (count_below, box_below) = merge_buckets(&counts[..split], &bounds[..split]);
+                let (count_above, box_above) = merge_buckets(&counts[split..], &bounds[split..]);
+                if count_below == 0 || count_above == 0 {
+                    continue;
+                }
+                let cost = TRAVERSAL_COST
+                    + (count_below as f32 * box_below.surface_area()
+                        + count_above as f32 * box_above.surface_area())
+                        / bounding_box.surface_area();
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, split, cost));
+                }
+            }
+        }
+
+        match best {
+            // Splitting would not be cheaper than intersecting every primitive.
+            Some((_, _, cost)) if n <= MAX_PRIMITIVES_IN_LEAF && cost >= n as f32 => {
+                BvhNode::leaf(bounding_box, primitives)
+            }
+            Some((axis, split, _)) => {
+                let low = centroid_bounds.min[axis];
+                let extent = centroid_bounds.max[axis] - low;
+                let (below, above): (Vec<_>, Vec<_>) = primitives
+                    .into_iter()
+                    .partition(|(b, _)| bucket_index(b.centroid()[axis], low, extent) < split);
+                BvhNode::branch(bounding_box, below, above)
+            }
+            // All centroids coincide: split in half, or stop if few enough.
+            None if n <= MAX_PRIMITIVES_IN_LEAF => BvhNode::leaf(bounding_box, primitives),
+            None => {
+                let above = primitives.split_off(n / 2);
+                BvhNode::branch(bounding_box, primitives, above)
+            }
+        }
+    }
+
+    fn leaf(bounding_box: Aabb, primitives: Vec<(Aabb, Box<dyn Hittable>)>) -> Self {
+        BvhNode {
+            bounding_box,
+            children: BvhChildren::Leaf(primitives.into_iter().map(|(_, h)| h).collect()),
+        }
+    }
+
+    fn branch(
+        bounding_box: Aabb,
+        below: Vec<(Aabb, Box<dyn Hittable>)>,
+        above: Vec<(Aabb, Box<dyn Hittable>)>,
+    ) -> Self {
+        BvhNode {
+            bounding_box,
+            children: BvhChildren::Branch(
+                Box::new(BvhNode::build(below)),
+                Box::new(BvhNode::build(above)),
+            ),
+        }
+    }
+}
+
+fn bucket_index(centroid: f32, low: f32, extent: f32) -> usize {
+    let i = (SAH_BUCKETS as f32 * (centroid - low) / extent) as usize;
+    i.min(SAH_BUCKETS - 1)
+}
+
+fn merge_buckets(counts: &[usize], bounds: &[Aabb]) -> (usize, Aabb) {
+    counts
+        .iter()
+        .zip(bounds.iter())
+        .fold((0, Aabb::empty()), |(count, acc), (c, b)| {
+            (count + c, aabb::surrounding_box(&acc, b))
+        })
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        if !self.bounding_box.hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        match &self.children {
+            BvhChildren::Leaf(hittables) => {
+                let mut result = None;
+                let mut closest_hit_distance = t_max;
+                for hittable in hittables.iter() {
+                    if let Some(hit) = hittable.hit(ray, t_min, closest_hit_distance) {
+                        closest_hit_distance = hit.t;
+                        result.replace(hit);
+                    }
+                }
+                result
+            }
+            BvhChildren::Branch(left, right) => {
+                let left_hit = left.hit(ray, t_min, t_max);
+                let closest_hit_distance = left_hit.as_ref().map_or(t_max, |hit| hit.t);
+                right.hit(ray, t_min, closest_hit_distance).or(left_hit)
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bounding_box)
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        match &self.children {
+            BvhChildren::Leaf(hittables) => hittables
+                .iter()
+                .flat_map(|hittable| hittable.emitters())
+                .collect(),
+            BvhChildren::Branch(left, right) => {
+                let mut emitters = left.emitters();
+                emitters.extend(right.emitters());
+                emitters
+            }
+        }
+    }
+}
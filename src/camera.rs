@@ -1,7 +1,7 @@
 use crate::ray::Ray;
 use crate::vec3::{self, Vec3};
 
-use rand::prelude::*;
+use crate::rng::random;
 use std::f32;
 
 #[derive(Debug)]
@@ -17,6 +17,7 @@ pub struct Camera {
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Vec3,
         look_at: Vec3,
@@ -53,6 +54,17 @@ impl Camera {
         }
     }
 
+    pub fn time_range(&self) -> (f32, f32) {
+        self.time_range
+    }
+
+    // Angle covered by a pixel at the center of an image `height` pixels high.
+    pub fn pixel_spread(&self, height: usize) -> f32 {
+        let to_center =
+            self.lower_left_corner + 0.5 * self.horizontal + 0.5 * self.vertical - self.origin;
+        self.vertical.length() / (to_center.length() * height as f32)
+    }
+
     pub fn ray_at(&self, s: f32, t: f32) -> Ray {
         let random_in_lens_disk = self.lens_radius * Vec3::random_in_unit_disk();
         let offset = self.u * random_in_lens_disk.x + self.v * random_in_lens_disk.y;
@@ -0,0 +1,175 @@
+use raytracer::{ImageFormat, RenderSettings, ToneMapOperator, BUILTIN_SCENES};
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: raytracer [OPTIONS]
+
+Options:
+  -o, --output <PATH>      Output image path [default: out.ppm]
+  -f, --format <FORMAT>    Output format: ppm, ppm-ascii, png, exr, exr-half,
+                           pfm, hdr [default: from extension]
+  -W, --width <PIXELS>     Image width [default: 400]
+  -H, --height <PIXELS>    Image height [default: 200]
+  -s, --samples <COUNT>    Samples per pixel [default: 50]
+  -d, --max-depth <COUNT>  Maximum number of bounces per path [default: 50]
+      --exposure <STOPS>   Exposure adjustment before tone mapping [default: 0]
+      --tone-map <OP>      Tone mapping for 8-bit formats: clamp, reinhard,
+                           reinhard-extended, aces, hable [default: clamp]
+      --white <RADIANCE>   White point of reinhard-extended [default: 4]
+      --seed <SEED>        Seed for scene generation and sampling [default: 0]
+  -j, --threads <COUNT>    Number of render threads [default: all cores]
+      --scene <SCENE>      Scene file (.json, .gltf or .glb) or built-in scene
+                           name [default: random]
+  -h, --help               Print this help
+";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneSource {
+    Builtin(String),
+    File(PathBuf),
+    Gltf(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub output: PathBuf,
+    // Inferred from the output extension when not given.
+    pub format: Option<ImageFormat>,
+    pub settings: RenderSettings,
+    pub threads: Option<usize>,
+    pub scene: SceneSource,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            output: PathBuf::from("out.ppm"),
+            format: None,
+            settings: RenderSettings::default(),
+            threads: None,
+            scene: SceneSource::Builtin("random".to_string()),
+        }
+    }
+}
+
+// Outcome of a successful parse: either options to render with, or a request for help.
+pub enum Command {
+    Render(Options),
+    Help,
+}
+
+pub fn parse<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = Options::default();
+    let mut white = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || match inline_value.clone().or_else(|| args.next()) {
+            Some(value) => Ok(value),
+            None => Err(format!("missing value for `{}`", flag)),
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--output" => options.output = PathBuf::from(value()?),
+            "-f" | "--format" => options.format = Some(image_format(&value()?)?),
+            "-W" | "--width" => options.settings.width = positive(&flag, &value()?)?,
+            "-H" | "--height" => options.settings.height = positive(&flag, &value()?)?,
+            "-s" | "--samples" => options.settings.samples_per_pixel = positive(&flag, &value()?)?,
+            "-d" | "--max-depth" => {
+                options.settings.max_depth = match number(&flag, &value()?)? {
+                    depth if depth < 0 => return Err(format!("`{}` must not be negative", flag)),
+                    depth => depth,
+                }
+            }
+            "--exposure" => options.settings.tone_mapping.exposure = number(&flag, &value()?)?,
+            "--tone-map" => options.settings.tone_mapping.operator = tone_map_operator(&value()?)?,
+            "--white" => white = Some(number::<f32>(&flag, &value()?)?),
+            "--seed" => options.settings.seed = number(&flag, &value()?)?,
+            "-j" | "--threads" => options.threads = Some(positive(&flag, &value()?)?),
+            "--scene" => options.scene = scene_source(&value()?)?,
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    match (&mut options.settings.tone_mapping.operator, white) {
+        (ToneMapOperator::ExtendedReinhard { white }, Some(value)) if value > 0.0 => *white = value,
+        (ToneMapOperator::ExtendedReinhard { .. }, Some(_)) => {
+            return Err("`--white` must be greater than 0".to_string())
+        }
+        (_, Some(_)) => return Err("`--white` requires `--tone-map reinhard-extended`".to_string()),
+        (_, None) => {}
+    }
+
+    if options.format.is_none() && ImageFormat::from_path(&options.output).is_none() {
+        return Err(format!(
+            "cannot infer the image format of `{}`, use `--format`",
+            options.output.display()
+        ));
+    }
+    Ok(Command::Render(options))
+}
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for `{}`", value, flag))
+}
+
+fn positive(flag: &str, value: &str) -> Result<usize, String> {
+    match number(flag, value)? {
+        0 => Err(format!("`{}` must be greater than 0", flag)),
+        n => Ok(n),
+    }
+}
+
+fn image_format(value: &str) -> Result<ImageFormat, String> {
+    match value {
+        "ppm" => Ok(ImageFormat::Ppm),
+        "ppm-ascii" => Ok(ImageFormat::PpmAscii),
+        "png" => Ok(ImageFormat::Png),
+        "exr" => Ok(ImageFormat::Exr),
+        "exr-half" => Ok(ImageFormat::ExrHalf),
+        "pfm" => Ok(ImageFormat::Pfm),
+        "hdr" => Ok(ImageFormat::Hdr),
+        _ => Err(format!("unknown image format `{}`", value)),
+    }
+}
+
+fn tone_map_operator(value: &str) -> Result<ToneMapOperator, String> {
+    match value {
+        "clamp" => Ok(ToneMapOperator::Clamp),
+        "reinhard" => Ok(ToneMapOperator::Reinhard),
+        "reinhard-extended" => Ok(ToneMapOperator::ExtendedReinhard { white: 4.0 }),
+        "aces" => Ok(ToneMapOperator::Aces),
+        "hable" => Ok(ToneMapOperator::Hable),
+        _ => Err(format!("unknown tone mapping operator `{}`", value)),
+    }
+}
+
+fn scene_source(value: &str) -> Result<SceneSource, String> {
+    if BUILTIN_SCENES.contains(&value) {
+        Ok(SceneSource::Builtin(value.to_string()))
+    } else if value.ends_with(".json") {
+        Ok(SceneSource::File(PathBuf::from(value)))
+    } else if value.ends_with(".gltf") || value.ends_with(".glb") {
+        Ok(SceneSource::Gltf(PathBuf::from(value)))
+    } else {
+        Err(format!(
+            "`{}` is neither a .json/.gltf/.glb scene file nor a built-in scene ({})",
+            value,
+            BUILTIN_SCENES.join(", ")
+        ))
+    }
+}
@@ -0,0 +1,151 @@
+use crate::aabb::Aabb;
+use crate::disk;
+use crate::frame::{self, Frame, LocalHit};
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Cone standing on a circular base of `radius` around `base`, with its tip at `apex`, closed by
+// the base. On the side, u goes around the axis and v from the base (0) to the apex (1); the
+// base has the polar coordinates of a `Disk`.
+#[derive(Clone)]
+pub struct Cone {
+    frame: Frame,
+    base: Vec3,
+    apex: Vec3,
+    radius: f32,
+    height: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Cone {
+    // Panics if `base` and `apex` are the same point.
+    pub fn new(base: Vec3, apex: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Cone {
+            frame: Frame::new(base, apex - base),
+            base,
+            apex,
+            radius,
+            height: (apex - base).length(),
+            material,
+        }
+    }
+
+    fn side_area(&self) -> f32 {
+        PI * self.radius * (self.radius * self.radius + self.height * self.height).sqrt()
+    }
+
+    fn base_area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    // Outward normal of the side, the same all along a line from the base to the apex.
+    fn side_normal(&self, phi: f32) -> Vec3 {
+        Vec3::unit_from(Vec3::new(
+            self.height * phi.cos(),
+            self.height * phi.sin(),
+            self.radius,
+        ))
+    }
+
+    // Intersection of a local ray with the side.
+    fn side_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<LocalHit> {
+        let (o, d) = (ray.origin, ray.direction);
+        // x^2 + y^2 = (k (h - z))^2, with k the radius shrinking per unit of height.
+        let k2 = (self.radius / self.height).powi(2);
+        let g = self.height - o.z;
+        let a = d.x * d.x + d.y * d.y - k2 * d.z * d.z;
+        let half_b = o.x * d.x + o.y * d.y + k2 * g * d.z;
+        let c = o.x * o.x + o.y * o.y - k2 * g * g;
+        let roots = if a.abs() < 1e-12 {
+            // The ray is parallel to the side, and crosses it once at most.
+            if half_b == 0.0 {
+                return None;
+            }
+            let t = -c / (2.0 * half_b);
+            [t, t]
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant <= 0.0 {
+                return None;
+            }
+            let root = discriminant.sqrt();
+            let (t0, t1) = ((-half_b - root) / a, (-half_b + root) / a);
+            [t0.min(t1), t0.max(t1)]
+        };
+        // The height check leaves out the mirrored cone above the apex.
+        roots
+            .iter()
+            .map(|&t| (t, ray.at(t)))
+            .find(|(t, point)| *t > t_min && *t < t_max && point.z >= 0.0 && point.z <= self.height)
+            .map(|(t, point)| {
+                let phi = frame::azimuth(point.x, point.y);
+                LocalHit {
+                    t,
+                    normal: self.side_normal(phi),
+                    uv: (phi / (2.0 * PI), point.z / self.height),
+                    dpdu: 2.0 * PI * Vec3::new(-point.y, point.x, 0.0),
+                    dpdv: Vec3::new(
+                        -self.radius * phi.cos(),
+                        -self.radius * phi.sin(),
+                        self.height,
+                    ),
+                }
+            })
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let local = self.frame.ray_to_local(ray);
+        let side = self.side_hit(&local, t_min, t_max);
+        let base = disk::disk_hit(&local, 0.0, self.radius, -1.0, t_min, t_max);
+        let hit = frame::closest(side, base)?;
+        Some(self.frame.hit_result(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(frame::circle_bounds(self.base, self.frame.axis(), self.radius).grow(self.apex))
+    }
+
+    fn area(&self) -> f32 {
+        self.side_area() + self.base_area()
+    }
+
+    // Picks the side or the base in proportion to its area, reusing the first coordinate
+    // within it.
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        let side = self.side_area() / self.area();
+        let (point, normal) = if u.0 < side {
+            // The circumference grows linearly away from the apex, and so does the density.
+            let s = (u.0 / side).sqrt();
+            let phi = 2.0 * PI * u.1;
+            (
+                Vec3::new(
+                    self.radius * s * phi.cos(),
+                    self.radius * s * phi.sin(),
+                    self.height * (1.0 - s),
+                ),
+                self.side_normal(phi),
+            )
+        } else {
+            let u0 = ((u.0 - side) / (1.0 - side)).clamp(0.0, 1.0 - f32::EPSILON);
+            (
+                disk::uniform_disk((u0, u.1), self.radius, 0.0),
+                Vec3::new(0.0, 0.0, -1.0),
+            )
+        };
+        Some((
+            self.frame.point_to_world(point),
+            self.frame.vector_to_world(normal),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
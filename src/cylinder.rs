@@ -0,0 +1,134 @@
+use crate::aabb::{self, Aabb};
+use crate::disk;
+use crate::frame::{self, Frame, LocalHit};
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Cylinder going from the center of its `base` cap to the center of its `top` cap, closed by
+// both caps. On the side, u goes around the axis and v from the base (0) to the top (1); the
+// caps have the polar coordinates of a `Disk`.
+#[derive(Clone)]
+pub struct Cylinder {
+    frame: Frame,
+    base: Vec3,
+    top: Vec3,
+    radius: f32,
+    height: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Cylinder {
+    // Panics if `base` and `top` are the same point.
+    pub fn new(base: Vec3, top: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Cylinder {
+            frame: Frame::new(base, top - base),
+            base,
+            top,
+            radius,
+            height: (top - base).length(),
+            material,
+        }
+    }
+
+    fn side_area(&self) -> f32 {
+        2.0 * PI * self.radius * self.height
+    }
+
+    fn cap_area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let local = self.frame.ray_to_local(ray);
+        let side = side_hit(&local, self.radius, self.height, t_min, t_max);
+        let base = disk::disk_hit(&local, 0.0, self.radius, -1.0, t_min, t_max);
+        let top = disk::disk_hit(&local, self.height, self.radius, 1.0, t_min, t_max);
+        let hit = frame::closest(frame::closest(side, base), top)?;
+        Some(self.frame.hit_result(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let axis = self.frame.axis();
+        Some(aabb::surrounding_box(
+            &frame::circle_bounds(self.base, axis, self.radius),
+            &frame::circle_bounds(self.top, axis, self.radius),
+        ))
+    }
+
+    fn area(&self) -> f32 {
+        self.side_area() + 2.0 * self.cap_area()
+    }
+
+    // Picks the side or a cap in proportion to its area, reusing the first coordinate within it.
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        let side = self.side_area() / self.area();
+        let (point, normal) = if u.0 < side {
+            let phi = 2.0 * PI * u.1;
+            let normal = Vec3::new(phi.cos(), phi.sin(), 0.0);
+            (
+                self.radius * normal + Vec3::new(0.0, 0.0, self.height * u.0 / side),
+                normal,
+            )
+        } else {
+            let u0 = ((u.0 - side) / (1.0 - side)).clamp(0.0, 1.0 - f32::EPSILON);
+            if u0 < 0.5 {
+                (
+                    disk::uniform_disk((2.0 * u0, u.1), self.radius, 0.0),
+                    Vec3::new(0.0, 0.0, -1.0),
+                )
+            } else {
+                (
+                    disk::uniform_disk((2.0 * u0 - 1.0, u.1), self.radius, self.height),
+                    Vec3::new(0.0, 0.0, 1.0),
+                )
+            }
+        };
+        Some((
+            self.frame.point_to_world(point),
+            self.frame.vector_to_world(normal),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
+
+// Intersection of a local ray with the side of the cylinder of `radius` around the z axis,
+// between heights 0 and `height`.
+fn side_hit(ray: &Ray, radius: f32, height: f32, t_min: f32, t_max: f32) -> Option<LocalHit> {
+    let (o, d) = (ray.origin, ray.direction);
+    let a = d.x * d.x + d.y * d.y;
+    // The ray is parallel to the axis.
+    if a == 0.0 {
+        return None;
+    }
+    let half_b = o.x * d.x + o.y * d.y;
+    let c = o.x * o.x + o.y * o.y - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant <= 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [(-half_b - root) / a, (-half_b + root) / a]
+        .iter()
+        .map(|&t| (t, ray.at(t)))
+        .find(|(t, point)| *t > t_min && *t < t_max && point.z >= 0.0 && point.z <= height)
+        .map(|(t, point)| LocalHit {
+            t,
+            normal: Vec3::new(point.x, point.y, 0.0) / radius,
+            uv: (
+                frame::azimuth(point.x, point.y) / (2.0 * PI),
+                point.z / height,
+            ),
+            dpdu: 2.0 * PI * Vec3::new(-point.y, point.x, 0.0),
+            dpdv: Vec3::new(0.0, 0.0, height),
+        })
+}
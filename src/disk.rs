@@ -0,0 +1,109 @@
+use crate::aabb::{Aabb, FLAT_PADDING};
+use crate::frame::{self, Frame, LocalHit};
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Flat disk facing along `normal`. Surface coordinates are polar: u goes around the normal and
+// v from the center (0) to the rim (1).
+#[derive(Clone)]
+pub struct Disk {
+    frame: Frame,
+    center: Vec3,
+    radius: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Disk {
+    // Panics if `normal` is zero.
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Disk {
+            frame: Frame::new(center, normal),
+            center,
+            radius,
+            material,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let hit = disk_hit(
+            &self.frame.ray_to_local(ray),
+            0.0,
+            self.radius,
+            1.0,
+            t_min,
+            t_max,
+        )?;
+        Some(self.frame.hit_result(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let bounds = frame::circle_bounds(self.center, self.frame.axis(), self.radius);
+        Some(bounds.padded(FLAT_PADDING))
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        let point = uniform_disk(u, self.radius, 0.0);
+        Some((self.frame.point_to_world(point), self.frame.axis()))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
+
+// Intersection of a local ray with the disk of `radius` around the z axis at height `z`, facing
+// +z if `facing` is 1 and -z if it is -1.
+pub(crate) fn disk_hit(
+    ray: &Ray,
+    z: f32,
+    radius: f32,
+    facing: f32,
+    t_min: f32,
+    t_max: f32,
+) -> Option<LocalHit> {
+    // The ray is parallel to the disk.
+    if ray.direction.z.abs() < 1e-12 {
+        return None;
+    }
+    let t = (z - ray.origin.z) / ray.direction.z;
+    if !(t > t_min && t < t_max) {
+        return None;
+    }
+    let point = ray.at(t);
+    let distance = (point.x * point.x + point.y * point.y).sqrt();
+    if distance > radius {
+        return None;
+    }
+    // Mirrored on the side facing -z, so that coordinates go counterclockwise from either side.
+    let phi = frame::azimuth(point.x, facing * point.y);
+    let dpdv = if distance > 0.0 {
+        Vec3::new(point.x, point.y, 0.0) * (radius / distance)
+    } else {
+        Vec3::new(radius, 0.0, 0.0)
+    };
+    Some(LocalHit {
+        t,
+        normal: Vec3::new(0.0, 0.0, facing),
+        uv: (phi / (2.0 * PI), distance / radius),
+        dpdu: 2.0 * PI * Vec3::new(-point.y, point.x, 0.0) * facing,
+        dpdv,
+    })
+}
+
+// Uniformly distributed local point of the disk of `radius` around the z axis at height `z`.
+pub(crate) fn uniform_disk(u: (f32, f32), radius: f32, z: f32) -> Vec3 {
+    let distance = radius * u.0.sqrt();
+    let phi = 2.0 * PI * u.1;
+    Vec3::new(distance * phi.cos(), distance * phi.sin(), z)
+}
@@ -0,0 +1,123 @@
+// Piecewise-constant distribution over [0, 1), with one bucket per function value.
+pub struct Distribution1D {
+    function: Vec<f32>,
+    // cdf[i] is the probability of falling below bucket i; cdf[len] is 1.
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    // Panics if `function` is empty. Negative values count as 0, and a function that is 0
+    // everywhere is sampled uniformly.
+    pub fn new(function: &[f32]) -> Self {
+        assert!(
+            !function.is_empty(),
+            "Cannot build a distribution over nothing."
+        );
+        let function: Vec<f32> = function.iter().map(|&f| f.max(0.0)).collect();
+        let n = function.len() as f32;
+        let mut cdf = Vec::with_capacity(function.len() + 1);
+        cdf.push(0.0);
+        for (i, f) in function.iter().enumerate() {
+            cdf.push(cdf[i] + f / n);
+        }
+        let integral = cdf[function.len()];
+        if integral > 0.0 {
+            for c in cdf.iter_mut() {
+                *c /= integral;
+            }
+        } else {
+            for (i, c) in cdf.iter_mut().enumerate() {
+                *c = i as f32 / n;
+            }
+        }
+        *cdf.last_mut().unwrap() = 1.0;
+        Distribution1D {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.function.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.function.is_empty()
+    }
+
+    // Average of the function over [0, 1).
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    // Maps `u` in [0, 1) to a point of [0, 1) with density `pdf`, and the bucket it is in.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        // Last bucket whose cdf is not above u, skipping empty buckets.
+        let index = self.cdf.partition_point(|&c| c <= u).clamp(1, self.len()) - 1;
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            ((u - self.cdf[index]) / width).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        let x = ((index as f32 + offset) / self.len() as f32).min(1.0 - f32::EPSILON);
+        (x, self.pdf_of(index), index)
+    }
+
+    // Density at `x` in [0, 1).
+    pub fn pdf(&self, x: f32) -> f32 {
+        self.pdf_of(self.bucket(x))
+    }
+
+    fn pdf_of(&self, index: usize) -> f32 {
+        if self.integral > 0.0 {
+            self.function[index] / self.integral
+        } else {
+            1.0
+        }
+    }
+
+    fn bucket(&self, x: f32) -> usize {
+        ((x * self.len() as f32) as isize).clamp(0, self.len() as isize - 1) as usize
+    }
+}
+
+// Piecewise-constant distribution over [0, 1)^2, sampled by picking a row from the marginal
+// distribution and then a column in that row.
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    // `function` holds `width` values per row. Panics if it is empty or not a whole number of
+    // rows.
+    pub fn new(function: &[f32], width: usize) -> Self {
+        assert!(
+            width > 0 && !function.is_empty() && function.len().is_multiple_of(width),
+            "Function values do not make whole rows."
+        );
+        let rows: Vec<Distribution1D> = function.chunks(width).map(Distribution1D::new).collect();
+        let marginal = Distribution1D::new(
+            &rows
+                .iter()
+                .map(Distribution1D::integral)
+                .collect::<Vec<_>>(),
+        );
+        Distribution2D { rows, marginal }
+    }
+
+    // Point (x, y) of [0, 1)^2, y going along the rows, and its density.
+    pub fn sample(&self, u: (f32, f32)) -> ((f32, f32), f32) {
+        let (y, row_pdf, row) = self.marginal.sample(u.1);
+        let (x, column_pdf, _) = self.rows[row].sample(u.0);
+        ((x, y), row_pdf * column_pdf)
+    }
+
+    pub fn pdf(&self, (x, y): (f32, f32)) -> f32 {
+        let row = self.marginal.bucket(y);
+        self.marginal.pdf_of(row) * self.rows[row].pdf(x)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::distribution::Distribution2D;
+use crate::framebuffer::Framebuffer;
+use crate::image;
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+
+// Light coming from an equirectangular (latitude-longitude) image surrounding the scene, such
+// as an HDRI. The center of the image is towards -Z before rotation, and its top row is
+// straight up.
+//
+// Directions are importance sampled in proportion to the luminance of the image, so that small
+// bright areas like the sun are found without having to be hit by chance.
+pub struct EnvironmentLight {
+    image: Framebuffer,
+    // Around +Y, counterclockwise seen from above, like any right-handed rotation (radians).
+    rotation: f32,
+    intensity: f32,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentLight {
+    // `rotation` is in degrees. Panics if the image is empty.
+    pub fn new(image: Framebuffer, rotation: f32, intensity: f32) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "Cannot light a scene with an empty image."
+        );
+        let (width, height) = (image.width(), image.height());
+
+        // Radiance is interpolated between texels, so each texel is weighted by its brightest
+        // neighbour so that no direction with light gets a zero density. Rows are weighted by
+        // the solid angle they cover, which shrinks towards the poles.
+        let luminance: Vec<f32> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| luminance(image.get(x, y)))
+            .collect();
+        let mut function = vec![0.0; width * height];
+        for y in 0..height {
+            let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+            for x in 0..width {
+                let mut brightest: f32 = 0.0;
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for dx in [width - 1, 0, 1].iter() {
+                        brightest = brightest.max(luminance[ny * width + (x + dx) % width]);
+                    }
+                }
+                function[y * width + x] = brightest * sin_theta;
+            }
+        }
+        let distribution = Distribution2D::new(&function, width);
+
+        EnvironmentLight {
+            image,
+            rotation: rotation.to_radians(),
+            intensity,
+            distribution,
+        }
+    }
+
+    // Reads a Radiance HDR or OpenEXR image (or anything `image::load_image` reads).
+    pub fn load(path: &Path, rotation: f32, intensity: f32) -> io::Result<Self> {
+        let image = image::load_image(path)?;
+        if image.width() == 0 || image.height() == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "empty image"));
+        }
+        Ok(EnvironmentLight::new(image, rotation, intensity))
+    }
+
+    // Radiance arriving along -`direction`, i.e. seen when looking towards `direction`.
+    pub fn radiance(&self, direction: Vec3) -> Vec3 {
+        let (s, t) = self.to_image(Vec3::unit_from(direction));
+        self.intensity * self.bilinear(s, t)
+    }
+
+    // Unit direction towards the light for `u` in [0, 1)^2, with its radiance and solid angle
+    // density. The density is 0 for the rare samples landing on a pole.
+    pub fn sample(&self, u: (f32, f32)) -> (Vec3, Vec3, f32) {
+        let ((s, t), pdf) = self.distribution.sample(u);
+        let direction = self.direction_at(s, t);
+        let sin_theta = (PI * t).sin();
+        let pdf = if sin_theta > 0.0 {
+            pdf / (2.0 * PI * PI * sin_theta)
+        } else {
+            0.0
+        };
+        (direction, self.intensity * self.bilinear(s, t), pdf)
+    }
+
+    // Solid angle density of `sample` returning `direction`.
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        let (s, t) = self.to_image(Vec3::unit_from(direction));
+        let sin_theta = (PI * t).sin();
+        if sin_theta > 0.0 {
+            self.distribution.pdf((s, t)) / (2.0 * PI * PI * sin_theta)
+        } else {
+            0.0
+        }
+    }
+
+    // Image coordinates in [0, 1)^2, with t going down from straight up.
+    fn to_image(&self, direction: Vec3) -> (f32, f32) {
+        let phi = direction.x.atan2(-direction.z) + self.rotation;
+        let s = (0.5 + phi / (2.0 * PI)).rem_euclid(1.0);
+        // atan2 keeps its precision near the poles, where acos(y) loses it.
+        let horizontal = (direction.x * direction.x + direction.z * direction.z).sqrt();
+        let t = horizontal.atan2(direction.y) / PI;
+        (s, t)
+    }
+
+    fn direction_at(&self, s: f32, t: f32) -> Vec3 {
+        let phi = (s - 0.5) * 2.0 * PI - self.rotation;
+        let theta = t * PI;
+        Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        )
+    }
+
+    // Wraps around horizontally and clamps at the poles.
+    fn bilinear(&self, s: f32, t: f32) -> Vec3 {
+        let (width, height) = (self.image.width(), self.image.height());
+        let x = s * width as f32 - 0.5;
+        let y = (t * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (dx, dy) = (x - x0, y - y0);
+        let x0 = (x0 as isize).rem_euclid(width as isize) as usize;
+        let x1 = (x0 + 1) % width;
+        let y0 = y0 as usize;
+        let y1 = (y0 + 1).min(height - 1);
+        (1.0 - dx) * (1.0 - dy) * self.image.get(x0, y0)
+            + dx * (1.0 - dy) * self.image.get(x1, y0)
+            + (1.0 - dx) * dy * self.image.get(x0, y1)
+            + dx * dy * self.image.get(x1, y1)
+    }
+}
+
+// Rec. 709 luminance of linear RGB.
+fn luminance(color: Vec3) -> f32 {
+    0.2126 * color.r() + 0.7152 * color.g() + 0.0722 * color.b()
+}
@@ -0,0 +1,118 @@
+use crate::aabb::Aabb;
+use crate::hittable::HitResult;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Orthonormal frame placing a shape in the scene: shapes with an axis of symmetry are
+// intersected in the local space of a frame whose z axis is along theirs.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Frame {
+    origin: Vec3,
+    x: Vec3,
+    y: Vec3,
+    z: Vec3,
+}
+
+impl Frame {
+    // Panics if `axis` is zero.
+    pub fn new(origin: Vec3, axis: Vec3) -> Self {
+        assert!(axis.squared_length() > 0.0, "Frame axis must not be zero.");
+        let z = Vec3::unit_from(axis);
+        // Duff et al., "Building an Orthonormal Basis, Revisited".
+        let sign = 1f32.copysign(z.z);
+        let a = -1.0 / (sign + z.z);
+        let b = z.x * z.y * a;
+        let x = Vec3::new(1.0 + sign * z.x * z.x * a, sign * b, -sign * z.x);
+        let y = Vec3::new(b, sign + z.y * z.y * a, -z.y);
+        Frame { origin, x, y, z }
+    }
+
+    pub fn axis(&self) -> Vec3 {
+        self.z
+    }
+
+    pub fn point_to_local(&self, point: Vec3) -> Vec3 {
+        self.vector_to_local(point - self.origin)
+    }
+
+    pub fn vector_to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            vec3::dot(&v, &self.x),
+            vec3::dot(&v, &self.y),
+            vec3::dot(&v, &self.z),
+        )
+    }
+
+    pub fn point_to_world(&self, point: Vec3) -> Vec3 {
+        self.origin + self.vector_to_world(point)
+    }
+
+    pub fn vector_to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.x + v.y * self.y + v.z * self.z
+    }
+
+    // The frame is rigid, so distances along the ray are the same in both spaces.
+    pub fn ray_to_local(&self, ray: &Ray) -> Ray {
+        Ray {
+            origin: self.point_to_local(ray.origin),
+            direction: self.vector_to_local(ray.direction),
+            ..*ray
+        }
+    }
+
+    pub fn hit_result(&self, ray: &Ray, hit: LocalHit, material: &Arc<dyn Material>) -> HitResult {
+        HitResult::with_uv(
+            hit.t,
+            ray.at(hit.t),
+            self.vector_to_world(hit.normal),
+            hit.uv,
+            Arc::clone(material),
+        )
+        .with_derivatives(
+            self.vector_to_world(hit.dpdu),
+            self.vector_to_world(hit.dpdv),
+        )
+    }
+}
+
+// Intersection found in the local space of a frame.
+pub(crate) struct LocalHit {
+    pub t: f32,
+    pub normal: Vec3,
+    pub uv: (f32, f32),
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+}
+
+// Keeps the closer of two intersections.
+pub(crate) fn closest(a: Option<LocalHit>, b: Option<LocalHit>) -> Option<LocalHit> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.t < a.t { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+// Angle around the z axis, in [0, 2 pi).
+pub(crate) fn azimuth(x: f32, y: f32) -> f32 {
+    let phi = y.atan2(x);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+// Tight box around a circle of `radius` centered on `center`, in the plane normal to the unit
+// vector `axis`.
+pub(crate) fn circle_bounds(center: Vec3, axis: Vec3, radius: f32) -> Aabb {
+    let extent = Vec3::new(
+        radius * (1.0 - axis.x * axis.x).max(0.0).sqrt(),
+        radius * (1.0 - axis.y * axis.y).max(0.0).sqrt(),
+        radius * (1.0 - axis.z * axis.z).max(0.0).sqrt(),
+    );
+    Aabb::new(center - extent, center + extent)
+}
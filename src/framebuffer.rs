@@ -0,0 +1,41 @@
+use crate::vec3::Vec3;
+
+// Image of linear radiance values, stored row by row starting from the top-left pixel.
+#[derive(Clone, Debug)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Vec3::default(); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Vec3 {
+        debug_assert!(x < self.width && y < self.height, "Pixel out of range.");
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Vec3) {
+        debug_assert!(x < self.width && y < self.height, "Pixel out of range.");
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn pixels(&self) -> &[Vec3] {
+        &self.pixels
+    }
+}
@@ -0,0 +1,460 @@
+use crate::camera::Camera;
+use crate::disk::Disk;
+use crate::hittable::Hittable;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metallic, SpotLight};
+use crate::mesh::{MeshBuffers, MeshError, MeshFace, TriangleMesh};
+use crate::sphere::Sphere;
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+
+use ::gltf::buffer::Source;
+use ::gltf::camera::Projection;
+use ::gltf::khr_lights_punctual::Kind;
+use ::gltf::mesh::Mode;
+use ::gltf::{Document, Gltf, Node};
+use base64::Engine;
+
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+// Radius of the spheres standing in for point and spot lights, relative to the scene's.
+const LIGHT_SIZE: f32 = 0.01;
+// Distance of directional lights, and the radius of the disks standing in for them, relative to
+// the scene's radius.
+const SUN_DISTANCE: f32 = 10.0;
+const SUN_SIZE: f32 = 0.1;
+
+// Contents of a glTF 2.0 file (.gltf with external or embedded buffers, or .glb).
+pub struct GltfScene {
+    // Every mesh instance of the scene, with node transforms applied, merged in a single mesh.
+    pub mesh: TriangleMesh,
+    // Emitters standing in for the punctual lights of the scene.
+    pub lights: Vec<Box<dyn Hittable>>,
+    cameras: Vec<GltfCamera>,
+}
+
+struct GltfCamera {
+    look_from: Vec3,
+    look_at: Vec3,
+    up: Vec3,
+    vertical_fov: f32, // degrees
+    aspect_ratio: Option<f32>,
+}
+
+// Punctual light in world space, with its color scaled by its intensity.
+enum GltfLight {
+    Point {
+        position: Vec3,
+        intensity: Vec3,
+    },
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        intensity: Vec3,
+        inner_angle: f32, // degrees
+        outer_angle: f32, // degrees
+    },
+    Directional {
+        direction: Vec3,
+        irradiance: Vec3,
+    },
+}
+
+impl GltfLight {
+    // Punctual lights have no surface, so they become small emitters, sized by the scene they
+    // light so that shadows stay sharp. Point and spot lights are spheres with the intensity of
+    // the light, and directional lights are distant disks giving the same irradiance. Intensities
+    // are taken as they are: a point light of 1 candela gives an irradiance of 1 at a distance
+    // of 1.
+    fn build(&self, center: Vec3, radius: f32) -> Box<dyn Hittable> {
+        // A sphere of radiance L has an intensity of L pi r^2 in every direction.
+        let sphere_radius = LIGHT_SIZE * radius;
+        let sphere_area = PI * sphere_radius * sphere_radius;
+        match *self {
+            GltfLight::Point {
+                position,
+                intensity,
+            } => Box::new(Sphere::new(
+                position,
+                sphere_radius,
+                Arc::new(DiffuseLight::new(intensity / sphere_area)),
+            )),
+            GltfLight::Spot {
+                position,
+                direction,
+                intensity,
+                inner_angle,
+                outer_angle,
+            } => Box::new(Sphere::new(
+                position,
+                sphere_radius,
+                Arc::new(SpotLight::new(
+                    intensity / sphere_area,
+                    direction,
+                    inner_angle,
+                    outer_angle,
+                )),
+            )),
+            GltfLight::Directional {
+                direction,
+                irradiance,
+            } => {
+                // The disk covers a small solid angle, from which its radiance gives the
+                // irradiance of the light.
+                let distance = SUN_DISTANCE * radius;
+                let disk_radius = SUN_SIZE * distance;
+                let solid_angle = 2.0
+                    * PI
+                    * (1.0 - distance / (distance * distance + disk_radius * disk_radius).sqrt());
+                Box::new(Disk::new(
+                    center - distance * direction,
+                    direction,
+                    disk_radius,
+                    Arc::new(DiffuseLight::new(irradiance / solid_angle)),
+                ))
+            }
+        }
+    }
+}
+
+impl GltfScene {
+    // First perspective camera of the scene. It keeps the aspect ratio it was authored with,
+    // if any, and uses `aspect_ratio` otherwise.
+    pub fn camera(&self, aspect_ratio: f32) -> Option<Camera> {
+        self.cameras.first().map(|camera| {
+            Camera::new(
+                camera.look_from,
+                camera.look_at,
+                camera.up,
+                camera.vertical_fov,
+                camera.aspect_ratio.unwrap_or(aspect_ratio),
+                0.0,
+                1.0,
+                (0.0, 0.0),
+            )
+        })
+    }
+}
+
+// Loads the default scene of a glTF file. PBR metallic-roughness materials are mapped to the
+// closest material we have (see `convert_material`), and primitives without a material get
+// `default_material`. Punctual lights are approximated by small emitters (see `GltfLight`).
+// Textures, skins, morph targets and animations are ignored.
+pub fn load_gltf(path: &Path, default_material: Arc<dyn Material>) -> Result<GltfScene, MeshError> {
+    let invalid = |message: String| MeshError::Invalid {
+        file: path.to_path_buf(),
+        message,
+    };
+
+    let data = fs::read(path).map_err(|error| MeshError::Io {
+        file: path.to_path_buf(),
+        error,
+    })?;
+    let Gltf { document, blob } = Gltf::from_slice(&data).map_err(|e| invalid(e.to_string()))?;
+    let buffers = load_buffers(path, &document, blob)?;
+
+    let mut materials = vec![default_material];
+    materials.extend(document.materials().map(convert_material));
+
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| invalid("no scene to render".to_string()))?;
+    let mut loader = Loader {
+        buffers: &buffers,
+        mesh: MeshBuffers::default(),
+        faces: Vec::new(),
+        cameras: Vec::new(),
+        lights: Vec::new(),
+    };
+    for node in scene.nodes() {
+        loader
+            .visit(&node, &Transform::identity())
+            .map_err(invalid)?;
+    }
+
+    let mesh = TriangleMesh::new(loader.mesh, loader.faces, materials);
+    let (center, radius) = match mesh.bounding_box(0.0, 0.0) {
+        Some(bounds) => (bounds.centroid(), 0.5 * bounds.extent().length()),
+        None => (Vec3::default(), 1.0),
+    };
+    Ok(GltfScene {
+        lights: loader
+            .lights
+            .iter()
+            .map(|light| light.build(center, radius))
+            .collect(),
+        mesh,
+        cameras: loader.cameras,
+    })
+}
+
+fn load_buffers(
+    path: &Path,
+    document: &Document,
+    mut blob: Option<Vec<u8>>,
+) -> Result<Vec<Vec<u8>>, MeshError> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+        let invalid = |message: String| MeshError::Invalid {
+            file: path.to_path_buf(),
+            message: format!("buffer {}: {}", buffer.index(), message),
+        };
+        let data = match buffer.source() {
+            Source::Bin => blob
+                .take()
+                .ok_or_else(|| invalid("missing binary chunk".to_string()))?,
+            Source::Uri(uri) if uri.starts_with("data:") => {
+                let encoded = match uri.split_once(";base64,") {
+                    Some((_, encoded)) => encoded,
+                    None => return Err(invalid("data URI is not base64 encoded".to_string())),
+                };
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| invalid(e.to_string()))?
+            }
+            Source::Uri(uri) => {
+                let file = directory.join(percent_decode(uri));
+                fs::read(&file).map_err(|error| MeshError::Io { file, error })?
+            }
+        };
+        if data.len() < buffer.length() {
+            return Err(invalid(format!(
+                "{} bytes long instead of {}",
+                data.len(),
+                buffer.length()
+            )));
+        }
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+// Relative URIs may escape characters, spaces in particular.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' {
+            uri.get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Transmissive materials become dielectrics, mostly metallic ones metals (roughness driving
+// the fuzz), and everything else is diffuse with the base color.
+fn convert_material(material: ::gltf::Material) -> Arc<dyn Material> {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+    let base_color = Vec3::new(r, g, b);
+    // Emissive surfaces become lights, losing whatever they also reflect.
+    let [r, g, b] = material.emissive_factor();
+    let emissive = material.emissive_strength().unwrap_or(1.0) * Vec3::new(r, g, b);
+    if emissive.squared_length() > 0.0 {
+        return Arc::new(DiffuseLight::new(emissive));
+    }
+    let transmission = material
+        .transmission()
+        .map_or(0.0, |transmission| transmission.transmission_factor());
+    if transmission >= 0.5 {
+        Arc::new(Dielectric::new(material.ior().unwrap_or(1.5)))
+    } else if pbr.metallic_factor() >= 0.5 {
+        Arc::new(Metallic::new(
+            base_color,
+            pbr.roughness_factor().clamp(0.0, 1.0),
+        ))
+    } else {
+        Arc::new(Lambertian::new(base_color))
+    }
+}
+
+// Walks the node hierarchy, flattening mesh instances into world space.
+struct Loader<'a> {
+    buffers: &'a [Vec<u8>],
+    mesh: MeshBuffers,
+    faces: Vec<MeshFace>,
+    cameras: Vec<GltfCamera>,
+    lights: Vec<GltfLight>,
+}
+
+impl<'a> Loader<'a> {
+    fn visit(&mut self, node: &Node, parent: &Transform) -> Result<(), String> {
+        // glTF stores matrices column by column. A node scaled to nothing can't be seen, and
+        // neither can its children.
+        let columns = node.transform().matrix();
+        let rows = std::array::from_fn(|i| std::array::from_fn(|j| columns[j][i]));
+        let transform = match Transform::from_matrix(rows) {
+            Some(local) => *parent * local,
+            None => return Ok(()),
+        };
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.add_primitive(&primitive, &transform)
+                    .map_err(|message| {
+                        format!(
+                            "mesh {} primitive {}: {}",
+                            mesh.index(),
+                            primitive.index(),
+                            message
+                        )
+                    })?;
+            }
+        }
+        if let Some(camera) = node.camera() {
+            // Orthographic cameras have no counterpart in `Camera`.
+            if let Projection::Perspective(perspective) = camera.projection() {
+                let look_from = transform.point(Vec3::default());
+                self.cameras.push(GltfCamera {
+                    look_from,
+                    look_at: look_from + transform.vector(Vec3::new(0.0, 0.0, -1.0)),
+                    up: transform.vector(Vec3::new(0.0, 1.0, 0.0)),
+                    vertical_fov: perspective.yfov().to_degrees(),
+                    aspect_ratio: perspective.aspect_ratio(),
+                });
+            }
+        }
+        if let Some(light) = node.light() {
+            let [r, g, b] = light.color();
+            let intensity = light.intensity() * Vec3::new(r, g, b);
+            let position = transform.point(Vec3::default());
+            // Lights shine down their local -z axis.
+            let direction = Vec3::unit_from(transform.vector(Vec3::new(0.0, 0.0, -1.0)));
+            self.lights.push(match light.kind() {
+                Kind::Point => GltfLight::Point {
+                    position,
+                    intensity,
+                },
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => GltfLight::Spot {
+                    position,
+                    direction,
+                    intensity,
+                    inner_angle: inner_cone_angle.to_degrees(),
+                    outer_angle: outer_cone_angle.to_degrees(),
+                },
+                Kind::Directional => GltfLight::Directional {
+                    direction,
+                    irradiance: intensity,
+                },
+            });
+        }
+        for child in node.children() {
+            self.visit(&child, &transform)?;
+        }
+        Ok(())
+    }
+
+    fn add_primitive(
+        &mut self,
+        primitive: &::gltf::Primitive,
+        transform: &Transform,
+    ) -> Result<(), String> {
+        let buffers = self.buffers;
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+
+        let positions: Vec<Vec3> = match reader.read_positions() {
+            Some(positions) => positions
+                .map(|p| transform.point(Vec3::new(p[0], p[1], p[2])))
+                .collect(),
+            None => return Err("missing positions".to_string()),
+        };
+        let normals: Option<Vec<Vec3>> = match reader.read_normals() {
+            Some(normals) => Some(
+                normals
+                    .map(|n| {
+                        let normal = transform.normal(Vec3::new(n[0], n[1], n[2]));
+                        if normal.squared_length() == 0.0 {
+                            return Err("zero-length normal".to_string());
+                        }
+                        Ok(Vec3::unit_from(normal))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            None => None,
+        };
+        // glTF puts the origin of texture coordinates at the top, and we put it at the bottom.
+        let uvs: Option<Vec<(f32, f32)>> = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(|uv| (uv[0], 1.0 - uv[1])).collect());
+        let indices: Vec<usize> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+            None => (0..positions.len()).collect(),
+        };
+        if let Some(&index) = indices.iter().find(|&&index| index >= positions.len()) {
+            return Err(format!("index {} out of range", index));
+        }
+        if normals.as_ref().is_some_and(|n| n.len() != positions.len())
+            || uvs.as_ref().is_some_and(|uv| uv.len() != positions.len())
+        {
+            return Err("vertex attributes of different lengths".to_string());
+        }
+
+        let triangles: Vec<[usize; 3]> = match primitive.mode() {
+            Mode::Triangles => indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect(),
+            Mode::TriangleStrip => (2..indices.len())
+                .map(|i| {
+                    // Every other triangle of a strip is flipped to keep a consistent winding.
+                    if i % 2 == 0 {
+                        [indices[i - 2], indices[i - 1], indices[i]]
+                    } else {
+                        [indices[i - 1], indices[i - 2], indices[i]]
+                    }
+                })
+                .collect(),
+            Mode::TriangleFan => (2..indices.len())
+                .map(|i| [indices[0], indices[i - 1], indices[i]])
+                .collect(),
+            // Points and lines have no surface to render.
+            _ => Vec::new(),
+        };
+
+        // A mirroring transform reverses the winding, and so the geometric normals.
+        let mirrored = transform.determinant() < 0.0;
+        let material = primitive.material().index().map_or(0, |index| index + 1);
+        let offset = self.mesh.positions.len();
+        for triangle in triangles {
+            let mut corners = triangle.map(|i| offset + i);
+            if mirrored {
+                corners.swap(1, 2);
+            }
+            self.faces.push(MeshFace {
+                positions: corners,
+                normals: normals.as_ref().map(|_| corners),
+                uvs: uvs.as_ref().map(|_| corners),
+                colors: None,
+                material,
+            });
+        }
+
+        // Keep the buffers aligned, so that a single index addresses all the attributes.
+        self.mesh.positions.extend(positions);
+        self.mesh.normals.resize(offset, Vec3::new(0.0, 0.0, 1.0));
+        self.mesh.normals.extend(normals.unwrap_or_default());
+        self.mesh.uvs.resize(offset, (0.0, 0.0));
+        self.mesh.uvs.extend(uvs.unwrap_or_default());
+        Ok(())
+    }
+}
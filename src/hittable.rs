@@ -1,27 +1,97 @@
+use crate::aabb::Aabb;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct HitResult {
     pub t: f32,
     pub point: Vec3,
     pub normal: Vec3,
-    pub material: Rc<dyn Material>,
+    // Surface parameterization at the hit point.
+    pub u: f32,
+    pub v: f32,
+    // Partial derivatives of the point with respect to u and v, zero when unknown.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    // Color interpolated from the vertices of a mesh, which tints diffuse materials.
+    pub vertex_color: Option<Vec3>,
+    // Whether light sampling can pick this point, which it can't on surfaces without a known
+    // area. Emission found elsewhere is only found by chance, and isn't weighed against it.
+    pub sampleable: bool,
+    pub material: Arc<dyn Material>,
 }
 
 impl HitResult {
-    pub fn new(t: f32, point: Vec3, normal: Vec3, material: Rc<dyn Material>) -> Self {
+    pub fn new(t: f32, point: Vec3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        HitResult::with_uv(t, point, normal, (0.0, 0.0), material)
+    }
+
+    pub fn with_uv(
+        t: f32,
+        point: Vec3,
+        normal: Vec3,
+        uv: (f32, f32),
+        material: Arc<dyn Material>,
+    ) -> Self {
         HitResult {
             t,
             point,
             normal,
+            u: uv.0,
+            v: uv.1,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            vertex_color: None,
+            sampleable: true,
             material,
         }
     }
+
+    pub fn with_derivatives(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self
+    }
+
+    pub fn with_vertex_color(mut self, color: Vec3) -> Self {
+        self.vertex_color = Some(color);
+        self
+    }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult>;
+
+    // Box enclosing the object over the whole [time0, time1] interval, if the object is bounded.
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb>;
+
+    // Surface area of objects that can be sampled with `sample_surface`, 0 for the others.
+    fn area(&self) -> f32 {
+        0.0
+    }
+
+    // Point spread uniformly over the surface at `time`, for `u` in [0, 1)^2, with the normal
+    // there.
+    fn sample_surface(&self, _u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        None
+    }
+
+    // Parts of the object with an emissive material that can be sampled, to be used as lights.
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        Vec::new()
+    }
+}
+
+// `emitters` of a surface made of a single material: the surface itself, if it emits.
+pub(crate) fn emitters_of<H: Hittable + Clone + 'static>(
+    surface: &H,
+    material: &dyn Material,
+) -> Vec<Arc<dyn Hittable>> {
+    if material.is_emissive() {
+        vec![Arc::new(surface.clone())]
+    } else {
+        Vec::new()
+    }
 }
@@ -0,0 +1,551 @@
+use crate::framebuffer::Framebuffer;
+use crate::tonemap::{self, ToneMapping};
+use crate::vec3::Vec3;
+
+use exr::prelude::{f16, Image, ReadChannels, ReadLayers, SpecificChannels, WritableImage};
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ImageFormat {
+    // Binary (P6) PPM.
+    Ppm,
+    // ASCII (P3) PPM.
+    PpmAscii,
+    Png,
+    // High dynamic range formats, storing unclamped linear radiance.
+    // OpenEXR with 32-bit float channels.
+    Exr,
+    // OpenEXR with 16-bit half float channels.
+    ExrHalf,
+    // Portable float map.
+    Pfm,
+    // Radiance RGBE.
+    Hdr,
+}
+
+impl ImageFormat {
+    // Format matching the extension of `path`; `.ppm` selects binary PPM and `.exr` float EXR.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "exr" => Some(ImageFormat::Exr),
+            "pfm" => Some(ImageFormat::Pfm),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None,
+        }
+    }
+}
+
+// Tone mapping only applies to the low dynamic range formats.
+pub fn save_image(
+    framebuffer: &Framebuffer,
+    path: &Path,
+    format: ImageFormat,
+    tone_mapping: &ToneMapping,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ImageFormat::Ppm => write_ppm(framebuffer, tone_mapping, &mut writer)?,
+        ImageFormat::PpmAscii => write_ppm_ascii(framebuffer, tone_mapping, &mut writer)?,
+        ImageFormat::Png => write_png(framebuffer, tone_mapping, &mut writer)?,
+        ImageFormat::Exr => write_exr(framebuffer, &mut writer)?,
+        ImageFormat::ExrHalf => write_exr_half(framebuffer, &mut writer)?,
+        ImageFormat::Pfm => write_pfm(framebuffer, &mut writer)?,
+        ImageFormat::Hdr => write_hdr(framebuffer, &mut writer)?,
+    }
+    writer.flush()
+}
+
+pub fn write_ppm<W: Write>(
+    framebuffer: &Framebuffer,
+    tone_mapping: &ToneMapping,
+    writer: &mut W,
+) -> io::Result<()> {
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    writer.write_all(&to_rgb8(framebuffer, tone_mapping))
+}
+
+pub fn write_ppm_ascii<W: Write>(
+    framebuffer: &Framebuffer,
+    tone_mapping: &ToneMapping,
+    writer: &mut W,
+) -> io::Result<()> {
+    write!(
+        writer,
+        "P3\n{} {}\n255\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for rgb in to_rgb8(framebuffer, tone_mapping).chunks(3) {
+        writeln!(writer, "{} {} {}", rgb[0], rgb[1], rgb[2])?;
+    }
+    Ok(())
+}
+
+pub fn write_png<W: Write>(
+    framebuffer: &Framebuffer,
+    tone_mapping: &ToneMapping,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(
+        writer,
+        framebuffer.width() as u32,
+        framebuffer.height() as u32,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header().map_err(to_io_error)?;
+    writer
+        .write_image_data(&to_rgb8(framebuffer, tone_mapping))
+        .map_err(to_io_error)?;
+    writer.finish().map_err(to_io_error)
+}
+
+fn to_io_error(e: png::EncodingError) -> io::Error {
+    match e {
+        png::EncodingError::IoError(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+// Reads a PNG, JPEG, Radiance HDR or OpenEXR image into linear values, picking the format
+// from the extension of `path`.
+pub fn load_image(path: &Path) -> io::Result<Framebuffer> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => read_png(&mut reader),
+        Some("jpg") | Some("jpeg") => read_jpeg(&mut reader),
+        Some("hdr") => read_hdr(&mut reader),
+        Some("exr") => read_exr(&mut reader),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unsupported image format",
+        )),
+    }
+}
+
+// 8 and 16-bit PNG images are assumed to be sRGB encoded, and alpha is ignored.
+pub fn read_png<R: Read>(reader: &mut R) -> io::Result<Framebuffer> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(decoding_to_io_error)?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).map_err(decoding_to_io_error)?;
+
+    let channels = info.color_type.samples();
+    let mut framebuffer = Framebuffer::new(info.width as usize, info.height as usize);
+    for y in 0..framebuffer.height() {
+        let row = &data[y * info.line_size..];
+        for x in 0..framebuffer.width() {
+            let pixel = &row[x * channels..];
+            let decode = |value: u8| tonemap::srgb_eotf(value as f32 / 255.0);
+            let color = match info.color_type {
+                png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                    Vec3::from(decode(pixel[0]))
+                }
+                _ => Vec3::new(decode(pixel[0]), decode(pixel[1]), decode(pixel[2])),
+            };
+            framebuffer.set(x, y, color);
+        }
+    }
+    Ok(framebuffer)
+}
+
+fn decoding_to_io_error(e: png::DecodingError) -> io::Error {
+    match e {
+        png::DecodingError::IoError(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+// Grayscale and RGB JPEG images, assumed to be sRGB encoded.
+pub fn read_jpeg<R: Read>(reader: &mut R) -> io::Result<Framebuffer> {
+    let mut decoder = jpeg_decoder::Decoder::new(reader);
+    let data = decoder.decode().map_err(|e| match e {
+        jpeg_decoder::Error::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    })?;
+    let info = decoder
+        .info()
+        .expect("JPEG information is available once decoded.");
+
+    let mut framebuffer = Framebuffer::new(info.width as usize, info.height as usize);
+    let decode = |value: f32| tonemap::srgb_eotf(value);
+    for (i, pixel) in (0..framebuffer.width() * framebuffer.height()).enumerate() {
+        let color = match info.pixel_format {
+            jpeg_decoder::PixelFormat::L8 => Vec3::from(decode(data[pixel] as f32 / 255.0)),
+            jpeg_decoder::PixelFormat::L16 => {
+                let value = u16::from_ne_bytes([data[2 * pixel], data[2 * pixel + 1]]);
+                Vec3::from(decode(value as f32 / 65535.0))
+            }
+            jpeg_decoder::PixelFormat::RGB24 => Vec3::new(
+                decode(data[3 * pixel] as f32 / 255.0),
+                decode(data[3 * pixel + 1] as f32 / 255.0),
+                decode(data[3 * pixel + 2] as f32 / 255.0),
+            ),
+            jpeg_decoder::PixelFormat::CMYK32 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "CMYK JPEG images are not supported",
+                ))
+            }
+        };
+        framebuffer.set(i % info.width as usize, i / info.width as usize, color);
+    }
+    Ok(framebuffer)
+}
+
+// Largest Radiance image read, 16384 x 16384 pixels.
+const MAX_HDR_PIXELS: usize = 1 << 28;
+
+// Radiance RGBE images, flat or run-length encoded, stored top to bottom (-Y H +X W).
+pub fn read_hdr<R: Read>(reader: &mut R) -> io::Result<Framebuffer> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut reader = BufReader::new(reader);
+
+    let mut line = String::new();
+    let mut header_lines = 0;
+    loop {
+        line.clear();
+        if read_header_line(&mut reader, &mut line)? == 0 {
+            return Err(invalid("truncated header"));
+        }
+        if header_lines == 0 && !line.starts_with("#?") {
+            return Err(invalid("not a Radiance HDR image"));
+        }
+        header_lines += 1;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid("unsupported pixel format"));
+            }
+        }
+    }
+    line.clear();
+    read_header_line(&mut reader, &mut line)?;
+    let (height, width) = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["-Y", height, "+X", width] => match (height.parse::<usize>(), width.parse::<usize>()) {
+            (Ok(height), Ok(width)) => (height, width),
+            _ => return Err(invalid("invalid resolution")),
+        },
+        _ => return Err(invalid("unsupported image orientation")),
+    };
+    // The resolution is checked before allocating anything, so that a corrupt header fails
+    // instead of exhausting memory.
+    match width.checked_mul(height) {
+        Some(pixels) if pixels <= MAX_HDR_PIXELS && width <= MAX_HDR_PIXELS => {}
+        _ => return Err(invalid("image too large")),
+    }
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    let mut scanline = vec![[0u8; 4]; width];
+    for y in 0..height {
+        read_hdr_scanline(&mut reader, &mut scanline)?;
+        for (x, rgbe) in scanline.iter().enumerate() {
+            framebuffer.set(x, y, from_rgbe(*rgbe));
+        }
+    }
+    Ok(framebuffer)
+}
+
+// Reads a newline terminated header line, without the newline.
+fn read_header_line<R: Read>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    let mut byte = [0];
+    let mut count = 0;
+    while reader.read(&mut byte)? == 1 {
+        count += 1;
+        if byte[0] == b'\n' {
+            break;
+        }
+        line.push(byte[0] as char);
+    }
+    Ok(count)
+}
+
+fn read_hdr_scanline<R: Read>(reader: &mut R, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "corrupt scanline");
+    let width = scanline.len();
+    let mut first = [0u8; 4];
+    reader.read_exact(&mut first)?;
+
+    if (8..=0x7fff).contains(&width)
+        && first[0] == 2
+        && first[1] == 2
+        && ((first[2] as usize) << 8 | first[3] as usize) == width
+    {
+        // Each component is stored separately, as runs and literal dumps.
+        for component in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let mut count = [0u8; 1];
+                reader.read_exact(&mut count)?;
+                let count = count[0] as usize;
+                if count > 128 {
+                    let count = count - 128;
+                    if x + count > width {
+                        return Err(invalid());
+                    }
+                    let mut value = [0u8; 1];
+                    reader.read_exact(&mut value)?;
+                    for pixel in scanline[x..x + count].iter_mut() {
+                        pixel[component] = value[0];
+                    }
+                    x += count;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(invalid());
+                    }
+                    let mut values = vec![0u8; count];
+                    reader.read_exact(&mut values)?;
+                    for (pixel, value) in scanline[x..x + count].iter_mut().zip(values) {
+                        pixel[component] = value;
+                    }
+                    x += count;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // Flat pixels, possibly with old-style runs: (1, 1, 1, n) repeats the previous pixel n
+    // times, with consecutive run markers giving the higher bytes of the count.
+    let mut pixel = first;
+    let mut x = 0;
+    let mut shift = 0;
+    loop {
+        if pixel[0] == 1 && pixel[1] == 1 && pixel[2] == 1 {
+            // The count can't take more than four bytes.
+            if x == 0 || shift > 24 {
+                return Err(invalid());
+            }
+            let count = (pixel[3] as usize) << shift;
+            if x + count > width {
+                return Err(invalid());
+            }
+            let previous = scanline[x - 1];
+            for value in scanline[x..x + count].iter_mut() {
+                *value = previous;
+            }
+            x += count;
+            shift += 8;
+        } else {
+            scanline[x] = pixel;
+            x += 1;
+            shift = 0;
+        }
+        if x == width {
+            return Ok(());
+        }
+        reader.read_exact(&mut pixel)?;
+    }
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Vec3 {
+    if rgbe[3] == 0 {
+        return Vec3::default();
+    }
+    // Mantissas were truncated when encoding, so decode to the middle of their interval.
+    let scale = 2f32.powi(rgbe[3] as i32 - 136);
+    Vec3::new(
+        (rgbe[0] as f32 + 0.5) * scale,
+        (rgbe[1] as f32 + 0.5) * scale,
+        (rgbe[2] as f32 + 0.5) * scale,
+    )
+}
+
+pub fn write_exr<W: Write + Seek>(framebuffer: &Framebuffer, writer: &mut W) -> io::Result<()> {
+    let channels = SpecificChannels::rgb(|position: exr::math::Vec2<usize>| {
+        let color = framebuffer.get(position.x(), position.y());
+        (color.r(), color.g(), color.b())
+    });
+    Image::from_channels((framebuffer.width(), framebuffer.height()), channels)
+        .write()
+        .to_buffered(writer)
+        .map_err(exr_to_io_error)
+}
+
+pub fn write_exr_half<W: Write + Seek>(
+    framebuffer: &Framebuffer,
+    writer: &mut W,
+) -> io::Result<()> {
+    let channels = SpecificChannels::rgb(|position: exr::math::Vec2<usize>| {
+        let color = framebuffer.get(position.x(), position.y());
+        (
+            f16::from_f32(color.r()),
+            f16::from_f32(color.g()),
+            f16::from_f32(color.b()),
+        )
+    });
+    Image::from_channels((framebuffer.width(), framebuffer.height()), channels)
+        .write()
+        .to_buffered(writer)
+        .map_err(exr_to_io_error)
+}
+
+// RGB channels of the first layer that has them, at full resolution.
+pub fn read_exr<R: Read + Seek>(reader: &mut R) -> io::Result<Framebuffer> {
+    let image = exr::prelude::read()
+        .no_deep_data()
+        .largest_resolution_level()
+        .rgb_channels(
+            |resolution, _| Framebuffer::new(resolution.width(), resolution.height()),
+            |framebuffer: &mut Framebuffer, position, (r, g, b): (f32, f32, f32)| {
+                framebuffer.set(position.x(), position.y(), Vec3::new(r, g, b))
+            },
+        )
+        .first_valid_layer()
+        .all_attributes()
+        .from_buffered(reader)
+        .map_err(exr_to_io_error)?;
+    Ok(image.layer_data.channel_data.pixels)
+}
+
+fn exr_to_io_error(e: exr::error::Error) -> io::Error {
+    match e {
+        exr::error::Error::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+// Little-endian PFM, whose rows go from the bottom of the image to the top.
+pub fn write_pfm<W: Write>(framebuffer: &Framebuffer, writer: &mut W) -> io::Result<()> {
+    write!(
+        writer,
+        "PF\n{} {}\n-1.0\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for y in (0..framebuffer.height()).rev() {
+        for x in 0..framebuffer.width() {
+            let color = framebuffer.get(x, y);
+            for value in [color.r(), color.g(), color.b()].iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Radiance RGBE with run-length encoded scanlines.
+pub fn write_hdr<W: Write>(framebuffer: &Framebuffer, writer: &mut W) -> io::Result<()> {
+    let width = framebuffer.width();
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        framebuffer.height(),
+        width
+    )?;
+
+    let mut scanline = Vec::with_capacity(4 * width);
+    for row in framebuffer.pixels().chunks(width) {
+        scanline.clear();
+        // Scanlines outside this range can't be run-length encoded.
+        if !(8..=0x7fff).contains(&width) {
+            for color in row {
+                scanline.extend_from_slice(&to_rgbe(*color));
+            }
+        } else {
+            let rgbe: Vec<[u8; 4]> = row.iter().map(|color| to_rgbe(*color)).collect();
+            scanline.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+            for component in 0..4 {
+                let channel: Vec<u8> = rgbe.iter().map(|pixel| pixel[component]).collect();
+                encode_rle(&channel, &mut scanline);
+            }
+        }
+        writer.write_all(&scanline)?;
+    }
+    Ok(())
+}
+
+// Shared exponent encoding: the mantissas are scaled so that the largest one fits in a byte.
+fn to_rgbe(color: Vec3) -> [u8; 4] {
+    let v = color.r().max(color.g()).max(color.b());
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    let mut exponent = v.log2().floor() as i32 + 1;
+    // Guard against rounding in log2 so that v / 2^exponent lands in [0.5, 1).
+    if v / 2f32.powi(exponent) >= 1.0 {
+        exponent += 1;
+    } else if v / 2f32.powi(exponent) < 0.5 {
+        exponent -= 1;
+    }
+    let scale = 256.0 / 2f32.powi(exponent);
+    [
+        (color.r().max(0.0) * scale) as u8,
+        (color.g().max(0.0) * scale) as u8,
+        (color.b().max(0.0) * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+// Encodes one channel of a scanline as runs (count > 128) of at least four repeated bytes,
+// separated by literal dumps (count <= 128).
+fn encode_rle(data: &[u8], out: &mut Vec<u8>) {
+    const MIN_RUN: usize = 4;
+    let n = data.len();
+    let mut current = 0;
+    while current < n {
+        // Find the start of the next run long enough to be worth encoding.
+        let mut run_start = current;
+        let mut run_length = 0;
+        let mut previous_run_length = 0;
+        while run_length < MIN_RUN && run_start < n {
+            run_start += run_length;
+            previous_run_length = run_length;
+            run_length = 1;
+            while run_start + run_length < n
+                && run_length < 127
+                && data[run_start + run_length] == data[run_start]
+            {
+                run_length += 1;
+            }
+        }
+
+        // A short run right before the long one is still cheaper as a run.
+        if previous_run_length > 1 && previous_run_length == run_start - current {
+            out.push((128 + previous_run_length) as u8);
+            out.push(data[current]);
+            current = run_start;
+        }
+
+        while current < run_start {
+            let count = (run_start - current).min(128);
+            out.push(count as u8);
+            out.extend_from_slice(&data[current..current + count]);
+            current += count;
+        }
+
+        if run_length >= MIN_RUN {
+            out.push((128 + run_length) as u8);
+            out.push(data[run_start]);
+            current += run_length;
+        }
+    }
+}
+
+// Display-ready 8-bit sRGB triplets, row by row from the top.
+fn to_rgb8(framebuffer: &Framebuffer, tone_mapping: &ToneMapping) -> Vec<u8> {
+    let mut data = Vec::with_capacity(3 * framebuffer.pixels().len());
+    for color in framebuffer.pixels() {
+        data.extend_from_slice(&tone_mapping.to_srgb8(*color));
+    }
+    data
+}
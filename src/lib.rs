@@ -1,15 +1,85 @@
+mod aabb;
+mod animation;
+mod background;
+mod bvh;
 mod camera;
+mod cone;
+mod cylinder;
+mod disk;
+mod distribution;
+mod environment;
+mod frame;
+mod framebuffer;
+mod gltf;
 mod hittable;
+mod image;
+mod light;
 mod material;
+mod mesh;
+mod mipmap;
+mod noise;
+mod obj;
+mod plane;
+mod ply;
+mod quad;
+mod quaternion;
 mod ray;
+mod renderer;
+mod rng;
+mod scene;
 mod sphere;
+mod stl;
+mod texture;
+mod tile;
+mod tonemap;
+mod torus;
+mod transform;
+mod triangle;
 mod vec3;
 mod world;
 
+pub use self::gltf::{load_gltf, GltfScene};
+pub use aabb::Aabb;
+pub use animation::{Animated, AnimatedTransform, Keyframe};
+pub use background::Background;
+pub use bvh::BvhNode;
 pub use camera::Camera;
+pub use cone::Cone;
+pub use cylinder::Cylinder;
+pub use disk::Disk;
+pub use distribution::{Distribution1D, Distribution2D};
+pub use environment::EnvironmentLight;
+pub use framebuffer::Framebuffer;
 pub use hittable::{HitResult, Hittable};
-pub use material::{Dielectric, Lambertian, Material, Metallic};
+pub use image::{
+    load_image, read_exr, read_hdr, read_jpeg, read_png, save_image, write_exr, write_exr_half,
+    write_hdr, write_pfm, write_png, write_ppm, write_ppm_ascii, ImageFormat,
+};
+pub use material::{
+    Dielectric, DiffuseLight, Lambertian, Material, Metallic, ScatterRecord, SpotLight,
+};
+pub use mesh::{load_mesh, MeshBuffers, MeshError, MeshFace, TriangleMesh};
+pub use mipmap::{MipMap, TextureFilter, WrapMode};
+pub use noise::{Perlin, Worley};
+pub use obj::load_obj;
+pub use plane::Plane;
+pub use ply::load_ply;
+pub use quad::{BoxShape, Quad};
+pub use quaternion::Quaternion;
 pub use ray::Ray;
+pub use renderer::{RenderSettings, Renderer};
+pub use rng::{random, seed_thread_rng};
+pub use scene::{Scene, SceneError, BUILTIN_SCENES};
 pub use sphere::{MovingSphere, Sphere};
+pub use stl::load_stl;
+pub use texture::{
+    CellsTexture, CheckerTexture, CloudsTexture, ConstantTexture, ImageTexture, MarbleTexture,
+    Texture, TextureFootprint, WoodTexture,
+};
+pub use tile::{tiles, Tile};
+pub use tonemap::{srgb_eotf, srgb_oetf, ToneMapOperator, ToneMapping};
+pub use torus::Torus;
+pub use transform::{Transform, Transformed};
+pub use triangle::Triangle;
 pub use vec3::{cross, dot, Vec3};
 pub use world::World;
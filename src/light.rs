@@ -0,0 +1,48 @@
+use crate::distribution::Distribution1D;
+use crate::hittable::Hittable;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+// Emitting surfaces of a scene. Emitters are picked in proportion to their area, so that every
+// point of every emitter is equally likely to be sampled: the density of a point is the same
+// whichever emitter it is on, and is known without finding out which one that is.
+#[derive(Default)]
+pub struct AreaLights {
+    emitters: Vec<Arc<dyn Hittable>>,
+    distribution: Option<Distribution1D>,
+    total_area: f32,
+}
+
+impl AreaLights {
+    // Emitters without area are left out, since they cannot be sampled.
+    pub fn extend(&mut self, emitters: Vec<Arc<dyn Hittable>>) {
+        self.emitters
+            .extend(emitters.into_iter().filter(|emitter| emitter.area() > 0.0));
+        if self.emitters.is_empty() {
+            return;
+        }
+        let areas: Vec<f32> = self.emitters.iter().map(|emitter| emitter.area()).collect();
+        self.total_area = areas.iter().sum();
+        self.distribution = Some(Distribution1D::new(&areas));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emitters.is_empty()
+    }
+
+    // Point on one of the emitters at `time`, for `u` in [0, 1)^3, with the normal there.
+    pub fn sample(&self, u: (f32, f32, f32), time: f32) -> Option<(Vec3, Vec3)> {
+        let (_, _, index) = self.distribution.as_ref()?.sample(u.0);
+        self.emitters[index].sample_surface((u.1, u.2), time)
+    }
+
+    // Area density of `sample`, the same for every point of every emitter.
+    pub fn area_pdf(&self) -> f32 {
+        if self.total_area > 0.0 {
+            1.0 / self.total_area
+        } else {
+            0.0
+        }
+    }
+}
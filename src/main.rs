@@ -1,69 +1,71 @@
-use raytracer::{Camera, Vec3, World};
+mod cli;
 
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use cli::{Command, Options, SceneSource};
+use raytracer::{ImageFormat, Renderer, Scene};
 
-use rand::prelude::*;
+use std::env;
+use std::error::Error;
+use std::process;
 
-fn main() -> Result<(), std::io::Error> {
-    let path = Path::new("out.ppm");
-    let mut file = File::create(&path)?;
+fn main() {
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, cli::USAGE);
+            process::exit(2);
+        }
+    };
 
-    let nx = 400;
-    let ny = 200;
-    let ns = 50;
+    if let Err(e) = run(&options) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
 
-    println!(
-        "Generating {}x{} image with {} samples per pixel... ",
-        nx, ny, ns
-    );
+fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
 
-    // Write PPM header
-    file.write_all(format!("P3\n{} {}\n255\n", nx, ny).as_bytes())?;
+    let settings = options.settings;
 
-    let world = World::random();
+    // Seed the main thread too, so that generated scenes are reproducible.
+    raytracer::seed_thread_rng(settings.seed);
+    let Scene { camera, mut world } = match &options.scene {
+        SceneSource::Builtin(name) => Scene::builtin(name, settings.aspect_ratio())
+            .ok_or_else(|| format!("unknown built-in scene `{}`", name))?,
+        SceneSource::File(path) => Scene::load(path, settings.aspect_ratio())
+            .map_err(|e| format!("{}: {}", path.display(), e))?,
+        SceneSource::Gltf(path) => Scene::from_gltf(path, settings.aspect_ratio())?,
+    };
+    let time_range = camera.time_range();
+    world.build_bvh(time_range.0, time_range.1);
 
-    let look_from = Vec3::new(13.0, 2.0, 3.0);
-    let look_at = Vec3::new(0.0, 0.0, 0.0);
-    let vertical_fov = 20.0;
-    let aspect_ratio = nx as f32 / ny as f32;
-    let aperture = 0.1;
-    let distance_to_focus = 10.0;
-    let time_range = (0.0, 1.0);
-    let camera = Camera::new(
-        look_from,
-        look_at,
-        Vec3::new(0.0, 1.0, 0.0),
-        vertical_fov,
-        aspect_ratio,
-        aperture,
-        distance_to_focus,
-        time_range,
+    println!(
+        "Generating {}x{} image with {} samples per pixel on {} threads... ",
+        settings.width,
+        settings.height,
+        settings.samples_per_pixel,
+        rayon::current_num_threads()
     );
 
-    for y in (0..ny).rev() {
-        for x in 0..nx {
-            let mut col = Vec3::default();
-            for _ in 0..ns {
-                let u = (x as f32 + random::<f32>()) / nx as f32;
-                let v = (y as f32 + random::<f32>()) / ny as f32;
-                let ray = camera.ray_at(u, v);
-                col += world.color(&ray, 0);
-            }
-            col /= ns as f32;
-
-            // Gamma correction (gamma 2)
-            col = Vec3::new(col[0].sqrt(), col[1].sqrt(), col[2].sqrt());
-
-            let ir = (255.9 * col[0]) as i32;
-            let ig = (255.9 * col[1]) as i32;
-            let ib = (255.9 * col[2]) as i32;
-
-            // Write pixel color
-            file.write_all(format!("{} {} {}\n", ir, ig, ib).as_bytes())?;
-        }
-    }
+    let framebuffer = Renderer::new(settings, camera, world).render();
+    let format = options
+        .format
+        .or_else(|| ImageFormat::from_path(&options.output))
+        .expect("Image format is validated by the command line parser.");
+    raytracer::save_image(
+        &framebuffer,
+        &options.output,
+        format,
+        &settings.tone_mapping,
+    )?;
 
     println!("Done!");
 
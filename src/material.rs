@@ -1,51 +1,149 @@
 use crate::hittable::HitResult;
 use crate::ray::Ray;
+use crate::texture::{ConstantTexture, Texture, TextureFootprint};
 use crate::vec3::{self, Vec3};
 
-use rand::prelude::*;
+use crate::rng::random;
 
-pub trait Material {
-    fn scatter(
-        &self,
-        ray: &Ray,
-        hit: &HitResult,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Direction sampled by `Material::scatter`, for light arriving along it and leaving back along
+// the incoming ray.
+#[derive(Copy, Clone, Debug)]
+pub struct ScatterRecord {
+    pub direction: Vec3,
+    // Scattering function times the cosine with the normal, divided by `pdf`: the factor to
+    // apply to the light coming from `direction`.
+    pub throughput: Vec3,
+    // Solid angle density of `direction`, 0 for specular scattering.
+    pub pdf: f32,
+    // Scattering in a discrete direction (mirror reflection, refraction), which lights cannot
+    // be sampled for.
+    pub is_specular: bool,
+}
+
+impl ScatterRecord {
+    pub fn new(direction: Vec3, throughput: Vec3, pdf: f32) -> Self {
+        ScatterRecord {
+            direction,
+            throughput,
+            pdf,
+            is_specular: false,
+        }
+    }
+
+    pub fn specular(direction: Vec3, throughput: Vec3) -> Self {
+        ScatterRecord {
+            direction,
+            throughput,
+            pdf: 0.0,
+            is_specular: true,
+        }
+    }
+}
+
+// Materials scatter light by sampling a direction in `scatter`. Those that scatter over a
+// continuous range of directions also give the density of that sampling (`pdf`) and the value
+// of their scattering function (`eval`), so that the integrator can sample lights directly and
+// weigh both strategies. Specular materials keep the defaults of 0.
+pub trait Material: Send + Sync {
+    // None if the ray is absorbed.
+    fn scatter(&self, ray: &Ray, hit: &HitResult) -> Option<ScatterRecord>;
+
+    // Scattering function times the cosine between `direction` and the normal, for light
+    // coming from `direction` and leaving back along `ray`.
+    fn eval(&self, _ray: &Ray, _hit: &HitResult, _direction: Vec3) -> Vec3 {
+        Vec3::default()
+    }
+
+    // Solid angle density with which `scatter` picks `direction`.
+    fn pdf(&self, _ray: &Ray, _hit: &HitResult, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    // Radiance given off at the hit point, towards where `ray` came from.
+    fn emitted(&self, _ray: &Ray, _hit: &HitResult) -> Vec3 {
+        Vec3::default()
+    }
+
+    // Whether `emitted` can be anything but black, making surfaces with this material lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
 }
 
 pub struct Lambertian {
-    albedo: Vec3,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Vec3) -> Self {
+        Lambertian::textured(Arc::new(ConstantTexture::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
         Lambertian { albedo }
     }
+
+    // The texture is tinted by the vertex colors of meshes that have them.
+    fn albedo(&self, ray: &Ray, hit: &HitResult) -> Vec3 {
+        let albedo =
+            self.albedo
+                .filtered_value(hit.u, hit.v, &hit.point, &TextureFootprint::at(ray, hit));
+        match hit.vertex_color {
+            Some(color) => albedo * color,
+            None => albedo,
+        }
+    }
 }
 
+// Directions are sampled with a cosine-weighted density around the normal on the side the ray
+// came from, which cancels out with the cosine of the scattering function.
 impl Material for Lambertian {
-    fn scatter(
-        &self,
-        ray: &Ray,
-        hit: &HitResult,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let target = hit.point + hit.normal + Vec3::random_in_unit_sphere();
-        *scattered = Ray::with_time(hit.point, target - hit.point, ray.time);
-        *attenuation = self.albedo;
-        true
+    fn scatter(&self, ray: &Ray, hit: &HitResult) -> Option<ScatterRecord> {
+        let normal = facing_normal(ray, hit);
+        let mut direction = normal + Vec3::random_unit_vector();
+        if direction.squared_length() < 1e-12 {
+            direction = normal;
+        }
+        let pdf = self.pdf(ray, hit, direction);
+        if pdf == 0.0 {
+            return None;
+        }
+        Some(ScatterRecord::new(direction, self.albedo(ray, hit), pdf))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitResult, direction: Vec3) -> Vec3 {
+        self.pdf(ray, hit, direction) * self.albedo(ray, hit)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitResult, direction: Vec3) -> f32 {
+        let cosine = vec3::dot(&Vec3::unit_from(direction), &facing_normal(ray, hit));
+        cosine.max(0.0) / PI
+    }
+}
+
+// Normal on the side of the surface the ray arrives from.
+fn facing_normal(ray: &Ray, hit: &HitResult) -> Vec3 {
+    if vec3::dot(&ray.direction, &hit.normal) > 0.0 {
+        -hit.normal
+    } else {
+        hit.normal
     }
 }
 
 pub struct Metallic {
-    albedo: Vec3,
+    albedo: Arc<dyn Texture>,
     fuzz: f32,
 }
 
 impl Metallic {
     pub fn new(albedo: Vec3, fuzz: f32) -> Self {
+        Metallic::textured(Arc::new(ConstantTexture::new(albedo)), fuzz)
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: f32) -> Self {
         Metallic {
             albedo,
             fuzz: if fuzz <= 1.0 { fuzz } else { 1.0 },
@@ -54,21 +152,17 @@ impl Metallic {
 }
 
 impl Material for Metallic {
-    fn scatter(
-        &self,
-        ray: &Ray,
-        hit: &HitResult,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
+    // Fuzzy reflections are not given a density, and count as specular.
+    fn scatter(&self, ray: &Ray, hit: &HitResult) -> Option<ScatterRecord> {
         let reflected = vec3::reflect(Vec3::unit_from(ray.direction), hit.normal);
-        *scattered = Ray::with_time(
-            hit.point,
-            reflected + self.fuzz * Vec3::random_in_unit_sphere(),
-            ray.time,
-        );
-        *attenuation = self.albedo;
-        vec3::dot(&scattered.direction, &hit.normal) > 0.0
+        let direction = reflected + self.fuzz * Vec3::random_in_unit_sphere();
+        if vec3::dot(&direction, &hit.normal) <= 0.0 {
+            return None;
+        }
+        let albedo =
+            self.albedo
+                .filtered_value(hit.u, hit.v, &hit.point, &TextureFootprint::at(ray, hit));
+        Some(ScatterRecord::specular(direction, albedo))
     }
 }
 
@@ -83,16 +177,7 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(
-        &self,
-        ray: &Ray,
-        hit: &HitResult,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let reflected = vec3::reflect(ray.direction, hit.normal);
-        *attenuation = Vec3::from(1.0); // Glassy surfaces absorb nothing.
-
+    fn scatter(&self, ray: &Ray, hit: &HitResult) -> Option<ScatterRecord> {
         let (outward_normal, ni, nt, cosine) = if vec3::dot(&ray.direction, &hit.normal) > 0.0 {
             (
                 -hit.normal,
@@ -110,19 +195,94 @@ impl Material for Dielectric {
             )
         };
 
-        let (reflection_probe, refracted) =
-            if let Some(refracted) = vec3::refract(ray.direction, outward_normal, ni, nt) {
-                (schlick(cosine, self.refraction_index), Some(refracted))
-            } else {
-                (1.0, None)
-            };
-
-        if random::<f32>() < reflection_probe {
-            *scattered = Ray::with_time(hit.point, reflected, ray.time);
-        } else if let Some(r) = refracted {
-            *scattered = Ray::with_time(hit.point, r, ray.time);
+        // Reflect with the Fresnel reflectance, or always under total internal reflection.
+        let direction = match vec3::refract(ray.direction, outward_normal, ni, nt) {
+            Some(refracted) if random::<f32>() >= schlick(cosine, self.refraction_index) => {
+                refracted
+            }
+            _ => vec3::reflect(ray.direction, hit.normal),
+        };
+        // Glassy surfaces absorb nothing.
+        Some(ScatterRecord::specular(direction, Vec3::from(1.0)))
+    }
+}
+
+// Emits light evenly in every direction, from both sides, and reflects none.
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Vec3) -> Self {
+        DiffuseLight::textured(Arc::new(ConstantTexture::new(emit)))
+    }
+
+    pub fn textured(emit: Arc<dyn Texture>) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _hit: &HitResult) -> Option<ScatterRecord> {
+        None
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitResult) -> Vec3 {
+        self.emit
+            .filtered_value(hit.u, hit.v, &hit.point, &TextureFootprint::at(ray, hit))
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
+
+// Emits light in a cone around `direction`, fading out between the inner and outer angles (in
+// degrees) from it, and reflects none. The intensity of a small sphere in any direction is
+// proportional to its radiance that way, so a sphere of it is a spot light.
+pub struct SpotLight {
+    emit: Vec3,
+    direction: Vec3,
+    cos_inner: f32,
+    cos_outer: f32,
+}
+
+impl SpotLight {
+    // Panics if `direction` is zero or unless 0 <= `inner_angle` <= `outer_angle`.
+    pub fn new(emit: Vec3, direction: Vec3, inner_angle: f32, outer_angle: f32) -> Self {
+        assert!(
+            direction.squared_length() > 0.0,
+            "Spot light direction must not be zero."
+        );
+        assert!(
+            0.0 <= inner_angle && inner_angle <= outer_angle,
+            "Spot light inner angle must be within the outer one."
+        );
+        SpotLight {
+            emit,
+            direction: Vec3::unit_from(direction),
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+}
+
+impl Material for SpotLight {
+    fn scatter(&self, _ray: &Ray, _hit: &HitResult) -> Option<ScatterRecord> {
+        None
+    }
+
+    // Smooth falloff from the glTF punctual lights extension.
+    fn emitted(&self, ray: &Ray, _hit: &HitResult) -> Vec3 {
+        let cosine = -vec3::dot(&Vec3::unit_from(ray.direction), &self.direction);
+        if cosine >= self.cos_inner {
+            return self.emit;
         }
+        let falloff = ((cosine - self.cos_outer) / (self.cos_inner - self.cos_outer)).max(0.0);
+        falloff * falloff * self.emit
+    }
 
+    fn is_emissive(&self) -> bool {
         true
     }
 }
@@ -0,0 +1,271 @@
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
+use crate::gltf;
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::obj;
+use crate::ply;
+use crate::ray::Ray;
+use crate::stl;
+use crate::triangle;
+use crate::vec3::Vec3;
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io {
+        file: PathBuf,
+        error: io::Error,
+    },
+    // Malformed statement in a text format; `line` is 1-based.
+    Parse {
+        file: PathBuf,
+        line: usize,
+        message: String,
+    },
+    // Malformed or unsupported content not tied to a line (binary data, headers...).
+    Invalid {
+        file: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io { file, error } => write!(f, "{}: {}", file.display(), error),
+            MeshError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file.display(), line, message),
+            MeshError::Invalid { file, message } => write!(f, "{}: {}", file.display(), message),
+        }
+    }
+}
+
+impl error::Error for MeshError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MeshError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// Loads a mesh file, picking the format from its extension (.obj, .ply, .stl, .gltf or .glb).
+// `material` is used wherever the file doesn't specify one. Only the geometry of glTF scenes
+// is kept.
+pub fn load_mesh(path: &Path, material: Arc<dyn Material>) -> Result<TriangleMesh, MeshError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("obj") => obj::load_obj(path, material),
+        Some("ply") => ply::load_ply(path, material),
+        Some("stl") => stl::load_stl(path, material),
+        Some("gltf") | Some("glb") => gltf::load_gltf(path, material).map(|scene| scene.mesh),
+        _ => Err(MeshError::Invalid {
+            file: path.to_path_buf(),
+            message: "unknown mesh format".to_string(),
+        }),
+    }
+}
+
+// Vertex attribute buffers shared by all the faces of a mesh.
+#[derive(Clone, Debug, Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f32, f32)>,
+    pub colors: Vec<Vec3>,
+}
+
+// Triangle of a mesh, as indices into the buffers and into the mesh materials. Attributes
+// are indexed separately, as in OBJ files; formats with a single index per vertex just
+// repeat it.
+#[derive(Copy, Clone, Debug)]
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub colors: Option<[usize; 3]>,
+    pub material: usize,
+}
+
+struct MeshData {
+    buffers: MeshBuffers,
+    faces: Vec<MeshFace>,
+    materials: Vec<Arc<dyn Material>>,
+}
+
+// Indexed triangle mesh. Geometry is shared, so clones are cheap and can be placed many times
+// in a scene.
+#[derive(Clone)]
+pub struct TriangleMesh {
+    bvh: Arc<BvhNode>,
+    triangle_count: usize,
+}
+
+impl TriangleMesh {
+    // Panics if a face refers to a missing vertex attribute or material.
+    pub fn new(
+        buffers: MeshBuffers,
+        faces: Vec<MeshFace>,
+        materials: Vec<Arc<dyn Material>>,
+    ) -> Self {
+        for face in faces.iter() {
+            assert!(
+                face.positions.iter().all(|&i| i < buffers.positions.len())
+                    && face
+                        .normals
+                        .is_none_or(|n| n.iter().all(|&i| i < buffers.normals.len()))
+                    && face
+                        .uvs
+                        .is_none_or(|uv| uv.iter().all(|&i| i < buffers.uvs.len()))
+                    && face
+                        .colors
+                        .is_none_or(|c| c.iter().all(|&i| i < buffers.colors.len()))
+                    && face.material < materials.len(),
+                "Mesh face index out of range."
+            );
+        }
+
+        let triangle_count = faces.len();
+        let data = Arc::new(MeshData {
+            buffers,
+            faces,
+            materials,
+        });
+        let triangles: Vec<Box<dyn Hittable>> = (0..triangle_count)
+            .map(|index| {
+                Box::new(MeshTriangle {
+                    data: Arc::clone(&data),
+                    index,
+                }) as Box<dyn Hittable>
+            })
+            .collect();
+        TriangleMesh {
+            bvh: Arc::new(BvhNode::new(triangles, 0.0, 0.0)),
+            triangle_count,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        self.bvh.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        if self.triangle_count == 0 {
+            None
+        } else {
+            self.bvh.bounding_box(time0, time1)
+        }
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        self.bvh.emitters()
+    }
+}
+
+#[derive(Clone)]
+struct MeshTriangle {
+    data: Arc<MeshData>,
+    index: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vec3; 3] {
+        let positions = &self.data.buffers.positions;
+        let face = &self.data.faces[self.index];
+        [
+            positions[face.positions[0]],
+            positions[face.positions[1]],
+            positions[face.positions[2]],
+        ]
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let vertices = self.vertices();
+        let (t, b1, b2) = triangle::intersect(&vertices, ray, t_min, t_max)?;
+
+        let buffers = &self.data.buffers;
+        let face = &self.data.faces[self.index];
+        let geometric_normal = triangle::geometric_normal(&vertices);
+        let normal = match face.normals {
+            Some(n) => {
+                let normals = [
+                    buffers.normals[n[0]],
+                    buffers.normals[n[1]],
+                    buffers.normals[n[2]],
+                ];
+                triangle::shading_normal(&normals, geometric_normal, b1, b2)
+            }
+            None => geometric_normal,
+        };
+        let uvs = match face.uvs {
+            Some(uv) => [buffers.uvs[uv[0]], buffers.uvs[uv[1]], buffers.uvs[uv[2]]],
+            None => triangle::BARYCENTRIC_UVS,
+        };
+        let us = [uvs[0].0, uvs[1].0, uvs[2].0];
+        let vs = [uvs[0].1, uvs[1].1, uvs[2].1];
+        let (dpdu, dpdv) = triangle::uv_derivatives(&vertices, &uvs);
+        let hit = HitResult::with_uv(
+            t,
+            ray.at(t),
+            normal,
+            (
+                triangle::interpolate(&us, b1, b2),
+                triangle::interpolate(&vs, b1, b2),
+            ),
+            Arc::clone(&self.data.materials[face.material]),
+        )
+        .with_derivatives(dpdu, dpdv);
+        Some(match face.colors {
+            Some(c) => {
+                let colors = [
+                    buffers.colors[c[0]],
+                    buffers.colors[c[1]],
+                    buffers.colors[c[2]],
+                ];
+                hit.with_vertex_color(triangle::interpolate(&colors, b1, b2))
+            }
+            None => hit,
+        })
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(triangle::bounding_box(&self.vertices()))
+    }
+
+    fn area(&self) -> f32 {
+        triangle::area(&self.vertices())
+    }
+
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        let vertices = self.vertices();
+        Some((
+            triangle::sample(&vertices, u),
+            triangle::geometric_normal(&vertices),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        let face = &self.data.faces[self.index];
+        hittable::emitters_of(self, self.data.materials[face.material].as_ref())
+    }
+}
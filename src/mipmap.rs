@@ -0,0 +1,296 @@
+use crate::framebuffer::Framebuffer;
+use crate::vec3::Vec3;
+
+// How texture coordinates outside of [0, 1] are brought back onto the image.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    // Repeats the image, flipping every other copy.
+    Mirror,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFilter {
+    // Closest texel of the full resolution image.
+    Nearest,
+    // Bilinear interpolation in the full resolution image.
+    Bilinear,
+    // Bilinear interpolation in the two pyramid levels closest to the footprint size.
+    Trilinear,
+    // Elliptically weighted average over the footprint, for sharp results at grazing angles.
+    Ewa,
+}
+
+// EWA footprints are made rounder than this, to bound the number of texels they cover.
+const MAX_ANISOTROPY: f32 = 8.0;
+// Falloff of the Gaussian filter over EWA ellipses.
+const EWA_ALPHA: f32 = 2.0;
+
+// Image pyramid, each level halving the resolution of the previous one down to a single texel.
+//
+// Lookups take texture coordinates with v pointing up, and footprints as the changes in (u, v)
+// along the two axes of the area to filter.
+pub struct MipMap {
+    levels: Vec<Framebuffer>,
+    wrap: WrapMode,
+}
+
+impl MipMap {
+    // Panics if the image is empty.
+    pub fn new(image: Framebuffer, wrap: WrapMode) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "Cannot build a MIP map of an empty image."
+        );
+        let mut levels = vec![image];
+        loop {
+            let last = levels.last().unwrap();
+            if last.width() == 1 && last.height() == 1 {
+                break;
+            }
+            let next = downsample(last);
+            levels.push(next);
+        }
+        MipMap { levels, wrap }
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height()
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn lookup(
+        &self,
+        filter: TextureFilter,
+        u: f32,
+        v: f32,
+        footprint: [(f32, f32); 2],
+    ) -> Vec3 {
+        match filter {
+            TextureFilter::Nearest => self.nearest(u, v),
+            TextureFilter::Bilinear => self.bilinear(0, u, v),
+            TextureFilter::Trilinear => {
+                let length = |(du, dv): (f32, f32)| (du * du + dv * dv).sqrt();
+                self.trilinear(u, v, length(footprint[0]).max(length(footprint[1])))
+            }
+            TextureFilter::Ewa => self.ewa(u, v, footprint[0], footprint[1]),
+        }
+    }
+
+    // Texel of a level, with coordinates wrapped according to the wrap mode.
+    pub fn texel(&self, level: usize, x: i64, y: i64) -> Vec3 {
+        let image = &self.levels[level];
+        let x = wrap(x, image.width(), self.wrap);
+        let y = wrap(y, image.height(), self.wrap);
+        image.get(x, y)
+    }
+
+    pub fn nearest(&self, u: f32, v: f32) -> Vec3 {
+        let (s, t) = self.to_texels(0, u, v);
+        self.texel(0, s.floor() as i64, t.floor() as i64)
+    }
+
+    pub fn bilinear(&self, level: usize, u: f32, v: f32) -> Vec3 {
+        // Texel centers are at half-integer coordinates.
+        let (s, t) = self.to_texels(level, u, v);
+        let (s, t) = (s - 0.5, t - 0.5);
+        let (x, y) = (s.floor(), t.floor());
+        let (ds, dt) = (s - x, t - y);
+        let (x, y) = (x as i64, y as i64);
+        (1.0 - ds) * (1.0 - dt) * self.texel(level, x, y)
+            + ds * (1.0 - dt) * self.texel(level, x + 1, y)
+            + (1.0 - ds) * dt * self.texel(level, x, y + 1)
+            + ds * dt * self.texel(level, x + 1, y + 1)
+    }
+
+    // Isotropic lookup over a footprint `width` wide in texture coordinates.
+    pub fn trilinear(&self, u: f32, v: f32, width: f32) -> Vec3 {
+        let level = self.level_of(width);
+        let last = self.levels.len() - 1;
+        if level <= 0.0 {
+            self.bilinear(0, u, v)
+        } else if level >= last as f32 {
+            self.texel(last, 0, 0)
+        } else {
+            let lower = level.floor() as usize;
+            let t = level - lower as f32;
+            (1.0 - t) * self.bilinear(lower, u, v) + t * self.bilinear(lower + 1, u, v)
+        }
+    }
+
+    // Gaussian-weighted average over the ellipse with the given axes, read from the level where
+    // the minor axis covers a few texels (Heckbert's EWA, as in pbrt).
+    pub fn ewa(&self, u: f32, v: f32, axis0: (f32, f32), axis1: (f32, f32)) -> Vec3 {
+        let length = |(du, dv): (f32, f32)| (du * du + dv * dv).sqrt();
+        let (major, mut minor) = if length(axis0) >= length(axis1) {
+            (axis0, axis1)
+        } else {
+            (axis1, axis0)
+        };
+        let major_length = length(major);
+        let mut minor_length = length(minor);
+
+        if minor_length * MAX_ANISOTROPY < major_length && minor_length > 0.0 {
+            let scale = major_length / (minor_length * MAX_ANISOTROPY);
+            minor = (scale * minor.0, scale * minor.1);
+            minor_length *= scale;
+        }
+        if minor_length == 0.0 {
+            return self.bilinear(0, u, v);
+        }
+
+        let level = self.level_of(minor_length).max(0.0);
+        let last = self.levels.len() - 1;
+        if level >= last as f32 {
+            return self.texel(last, 0, 0);
+        }
+        let lower = level.floor() as usize;
+        let t = level - lower as f32;
+        let result = self.ewa_level(lower, u, v, major, minor);
+        if t == 0.0 {
+            result
+        } else {
+            (1.0 - t) * result + t * self.ewa_level(lower + 1, u, v, major, minor)
+        }
+    }
+
+    fn ewa_level(
+        &self,
+        level: usize,
+        u: f32,
+        v: f32,
+        axis0: (f32, f32),
+        axis1: (f32, f32),
+    ) -> Vec3 {
+        let image = &self.levels[level];
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let (s, t) = self.to_texels(level, u, v);
+        let (s, t) = (s - 0.5, t - 0.5);
+        // Texel rows go down while v goes up.
+        let axis0 = (axis0.0 * width, -axis0.1 * height);
+        let axis1 = (axis1.0 * width, -axis1.1 * height);
+
+        // Implicit equation of the ellipse, A s^2 + B s t + C t^2 < 1, enlarged by a texel so
+        // that it never falls between texel centers.
+        let mut a = axis0.1 * axis0.1 + axis1.1 * axis1.1 + 1.0;
+        let mut b = -2.0 * (axis0.0 * axis0.1 + axis1.0 * axis1.1);
+        let mut c = axis0.0 * axis0.0 + axis1.0 * axis1.0 + 1.0;
+        let inverse_f = 1.0 / (a * c - b * b * 0.25);
+        a *= inverse_f;
+        b *= inverse_f;
+        c *= inverse_f;
+
+        // Bounding box of the ellipse.
+        let determinant = -b * b + 4.0 * a * c;
+        let inverse_determinant = 1.0 / determinant;
+        let s_radius = 2.0 * inverse_determinant * (determinant * c).sqrt();
+        let t_radius = 2.0 * inverse_determinant * (determinant * a).sqrt();
+        let s0 = (s - s_radius).ceil() as i64;
+        let s1 = (s + s_radius).floor() as i64;
+        let t0 = (t - t_radius).ceil() as i64;
+        let t1 = (t + t_radius).floor() as i64;
+
+        let mut sum = Vec3::default();
+        let mut weight_sum = 0.0;
+        for y in t0..=t1 {
+            let dt = y as f32 - t;
+            for x in s0..=s1 {
+                let ds = x as f32 - s;
+                let r2 = a * ds * ds + b * ds * dt + c * dt * dt;
+                if r2 < 1.0 {
+                    let weight = (-EWA_ALPHA * r2).exp() - (-EWA_ALPHA).exp();
+                    sum += weight * self.texel(level, x, y);
+                    weight_sum += weight;
+                }
+            }
+        }
+        if weight_sum > 0.0 {
+            sum / weight_sum
+        } else {
+            self.bilinear(level, u, v)
+        }
+    }
+
+    // Continuous level at which a footprint `width` wide covers about one texel.
+    fn level_of(&self, width: f32) -> f32 {
+        let resolution = self.width().max(self.height()) as f32;
+        (width * resolution).max(1e-8).log2()
+    }
+
+    // Texel space coordinates, with the origin at the top-left corner of the level.
+    fn to_texels(&self, level: usize, u: f32, v: f32) -> (f32, f32) {
+        let image = &self.levels[level];
+        (u * image.width() as f32, (1.0 - v) * image.height() as f32)
+    }
+}
+
+fn wrap(coordinate: i64, size: usize, mode: WrapMode) -> usize {
+    let size = size as i64;
+    let wrapped = match mode {
+        WrapMode::Repeat => coordinate.rem_euclid(size),
+        WrapMode::Clamp => coordinate.clamp(0, size - 1),
+        WrapMode::Mirror => {
+            let period = coordinate.rem_euclid(2 * size);
+            if period < size {
+                period
+            } else {
+                2 * size - 1 - period
+            }
+        }
+    };
+    wrapped as usize
+}
+
+// Halves the resolution (rounding down, but to no less than 1), averaging the area covered by
+// each new texel so that odd sizes don't shift the image.
+fn downsample(image: &Framebuffer) -> Framebuffer {
+    let width = (image.width() / 2).max(1);
+    let height = (image.height() / 2).max(1);
+
+    let mut columns = Framebuffer::new(width, image.height());
+    for y in 0..image.height() {
+        let row: Vec<Vec3> = (0..image.width()).map(|x| image.get(x, y)).collect();
+        for (x, value) in box_filter(&row, width).into_iter().enumerate() {
+            columns.set(x, y, value);
+        }
+    }
+
+    let mut result = Framebuffer::new(width, height);
+    for x in 0..width {
+        let column: Vec<Vec3> = (0..image.height()).map(|y| columns.get(x, y)).collect();
+        for (y, value) in box_filter(&column, height).into_iter().enumerate() {
+            result.set(x, y, value);
+        }
+    }
+    result
+}
+
+// Resamples `values` to `count` values, each the average of the span of input it covers.
+fn box_filter(values: &[Vec3], count: usize) -> Vec<Vec3> {
+    let scale = values.len() as f32 / count as f32;
+    (0..count)
+        .map(|i| {
+            let start = i as f32 * scale;
+            let end = start + scale;
+            let mut sum = Vec3::default();
+            for (j, value) in values
+                .iter()
+                .enumerate()
+                .take(end.ceil() as usize)
+                .skip(start.floor() as usize)
+            {
+                let overlap = end.min(j as f32 + 1.0) - start.max(j as f32);
+                sum += overlap * *value;
+            }
+            sum / scale
+        })
+        .collect()
+}
@@ -0,0 +1,190 @@
+use crate::vec3::{self, Vec3};
+
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+const POINT_COUNT: usize = 256;
+
+// Ken Perlin's gradient noise. Lattice tables are generated from a seed of their own, so that
+// the same seed gives the same pattern whatever else draws random numbers.
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    permutations: [Vec<usize>; 3],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| loop {
+                let v = Vec3::new(
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                );
+                // Rejecting the corners of the cube keeps directions uniform.
+                let squared_length = v.squared_length();
+                if squared_length > 1e-6 && squared_length <= 1.0 {
+                    break Vec3::unit_from(v);
+                }
+            })
+            .collect();
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        let permutations = [permutation(), permutation(), permutation()];
+        Perlin {
+            gradients,
+            permutations,
+        }
+    }
+
+    // Noise in [-1, 1], 0 on the lattice points, smooth everywhere else: gradient contributions
+    // of the 8 corners of the lattice cell are blended trilinearly, with Hermite-smoothed weights.
+    pub fn noise(&self, point: &Vec3) -> f32 {
+        let floor = [point.x.floor(), point.y.floor(), point.z.floor()];
+        let fraction = [point.x - floor[0], point.y - floor[1], point.z - floor[2]];
+        let cell = [floor[0] as i64, floor[1] as i64, floor[2] as i64];
+        let smooth = fraction.map(|t| t * t * (3.0 - 2.0 * t));
+
+        let mut sum = 0.0;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let mut hash = 0;
+            let mut weight = 1.0;
+            for axis in 0..3 {
+                let index = (cell[axis] + offset[axis] as i64) as usize & (POINT_COUNT - 1);
+                hash ^= self.permutations[axis][index];
+                weight *= if offset[axis] == 1 {
+                    smooth[axis]
+                } else {
+                    1.0 - smooth[axis]
+                };
+            }
+            let to_point = Vec3::new(
+                fraction[0] - offset[0] as f32,
+                fraction[1] - offset[1] as f32,
+                fraction[2] - offset[2] as f32,
+            );
+            sum += weight * vec3::dot(&self.gradients[hash], &to_point);
+        }
+        sum
+    }
+
+    // Sum of absolute noise over `octaves` octaves of doubling frequency and halving amplitude.
+    pub fn turbulence(&self, point: &Vec3, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut point = *point;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(&point).abs();
+            point *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+
+    // Fractional Brownian motion: signed noise summed over `octaves` octaves, each `lacunarity`
+    // times the frequency and `gain` times the amplitude of the previous one.
+    pub fn fbm(&self, point: &Vec3, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut point = *point;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(&point);
+            point *= lacunarity;
+            amplitude *= gain;
+        }
+        sum
+    }
+}
+
+// Steven Worley's cellular noise, with one feature point per unit cell.
+pub struct Worley {
+    seed: u64,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Worley { seed }
+    }
+
+    // Distances from `point` to the closest and second closest feature points (F1 and F2).
+    pub fn distances(&self, point: &Vec3) -> (f32, f32) {
+        let cell = [
+            point.x.floor() as i64,
+            point.y.floor() as i64,
+            point.z.floor() as i64,
+        ];
+        let offset = [
+            point.x - cell[0] as f32,
+            point.y - cell[1] as f32,
+            point.z - cell[2] as f32,
+        ];
+        let mut closest = (f32::MAX, f32::MAX);
+        let visit = |closest: &mut (f32, f32), d: [i64; 3]| {
+            let neighbour = [cell[0] + d[0], cell[1] + d[1], cell[2] + d[2]];
+            let distance = (self.feature_point(neighbour) - *point).length();
+            if distance < closest.0 {
+                *closest = (distance, closest.0);
+            } else if distance < closest.1 {
+                closest.1 = distance;
+            }
+        };
+        // The 3x3x3 neighbourhood always holds F1, but F2 can be up to two cells further out.
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    visit(&mut closest, [dx, dy, dz]);
+                }
+            }
+        }
+        // Its own cell and a neighbour put two points within sqrt(6) < 3, so cells more than
+        // three away can't hold F2, and nearer ones are skipped once they're further than it.
+        for dz in -3i64..=3 {
+            for dy in -3i64..=3 {
+                for dx in -3i64..=3 {
+                    let d = [dx, dy, dz];
+                    if d.iter().all(|c| c.abs() <= 1) {
+                        continue;
+                    }
+                    let gap = |i: usize| match d[i] {
+                        0 => 0.0,
+                        c if c > 0 => c as f32 - offset[i],
+                        c => offset[i] - (c + 1) as f32,
+                    };
+                    let (x, y, z) = (gap(0), gap(1), gap(2));
+                    if x * x + y * y + z * z < closest.1 * closest.1 {
+                        visit(&mut closest, d);
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    fn feature_point(&self, cell: [i64; 3]) -> Vec3 {
+        let mut hash = self.seed;
+        for &c in cell.iter() {
+            hash = mix(hash ^ c as u64);
+        }
+        // Three 21-bit fractions from a single hash.
+        let fraction = |bits: u64| (bits & 0x1f_ffff) as f32 / (1 << 21) as f32;
+        Vec3::new(
+            cell[0] as f32 + fraction(hash),
+            cell[1] as f32 + fraction(hash >> 21),
+            cell[2] as f32 + fraction(hash >> 42),
+        )
+    }
+}
+
+// Finalizer of the SplitMix64 generator, spreading every input bit over the output.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
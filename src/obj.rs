@@ -0,0 +1,308 @@
+use crate::material::{Dielectric, Lambertian, Material, Metallic};
+use crate::mesh::{MeshBuffers, MeshError, MeshFace, TriangleMesh};
+use crate::vec3::Vec3;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::{FromStr, SplitWhitespace};
+use std::sync::Arc;
+
+// Loads a Wavefront OBJ file as a single mesh. Materials come from the MTL libraries it
+// references (resolved relative to the OBJ file), and faces before any `usemtl` get
+// `default_material`. Polygons are triangulated as fans.
+pub fn load_obj(
+    path: &Path,
+    default_material: Arc<dyn Material>,
+) -> Result<TriangleMesh, MeshError> {
+    let source = read(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut buffers = MeshBuffers::default();
+    let mut faces = Vec::new();
+    let mut materials = vec![default_material];
+    let mut library: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut material_indices: HashMap<String, usize> = HashMap::new();
+    let mut current_material = 0;
+
+    for (i, line) in source.lines().enumerate() {
+        let mut parser = LineParser::new(path, i + 1, line);
+        let keyword = match parser.keyword() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        match keyword {
+            "v" => {
+                buffers.positions.push(parser.vec3()?);
+            }
+            "vn" => {
+                let normal = parser.vec3()?;
+                if normal.squared_length() == 0.0 {
+                    return Err(parser.error("zero-length normal"));
+                }
+                buffers.normals.push(normal);
+            }
+            "vt" => {
+                let u = parser.number()?;
+                // The second coordinate is optional, and a third one (w) is ignored.
+                let v = parser.optional_number()?.unwrap_or(0.0);
+                buffers.uvs.push((u, v));
+            }
+            "f" => {
+                let mut corners = Vec::new();
+                for token in parser.remaining() {
+                    corners.push(parser.face_corner(token, &buffers)?);
+                }
+                if corners.len() < 3 {
+                    return Err(parser.error("face with fewer than 3 vertices"));
+                }
+                let has_normals = corners.iter().all(|c| c.normal.is_some());
+                let has_uvs = corners.iter().all(|c| c.uv.is_some());
+                for k in 1..corners.len() - 1 {
+                    let fan = [corners[0], corners[k], corners[k + 1]];
+                    faces.push(MeshFace {
+                        positions: [fan[0].position, fan[1].position, fan[2].position],
+                        normals: if has_normals {
+                            Some([
+                                fan[0].normal.unwrap(),
+                                fan[1].normal.unwrap(),
+                                fan[2].normal.unwrap(),
+                            ])
+                        } else {
+                            None
+                        },
+                        uvs: if has_uvs {
+                            Some([fan[0].uv.unwrap(), fan[1].uv.unwrap(), fan[2].uv.unwrap()])
+                        } else {
+                            None
+                        },
+                        colors: None,
+                        material: current_material,
+                    });
+                }
+            }
+            "mtllib" => {
+                for name in parser.remaining() {
+                    library.extend(load_mtl(&directory.join(name))?);
+                }
+            }
+            "usemtl" => {
+                let name = parser.name()?;
+                current_material = match material_indices.get(name) {
+                    Some(&index) => index,
+                    None => match library.get(name) {
+                        Some(material) => {
+                            materials.push(Arc::clone(material));
+                            material_indices.insert(name.to_string(), materials.len() - 1);
+                            materials.len() - 1
+                        }
+                        None => return Err(parser.error(&format!("unknown material `{}`", name))),
+                    },
+                };
+            }
+            // Grouping and smoothing statements don't affect the geometry, and points, lines
+            // and free-form surfaces aren't supported.
+            _ => {}
+        }
+    }
+
+    Ok(TriangleMesh::new(buffers, faces, materials))
+}
+
+// Parameters of a material in an MTL file, before choosing the closest material we support.
+struct MtlMaterial {
+    diffuse: Vec3,
+    specular: Vec3,
+    shininess: f32,
+    dissolve: f32,
+    refraction_index: f32,
+    illumination: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        MtlMaterial {
+            diffuse: Vec3::from(0.8),
+            specular: Vec3::default(),
+            shininess: 0.0,
+            dissolve: 1.0,
+            refraction_index: 1.5,
+            illumination: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // Transparent materials become dielectrics, specular-dominated ones metals (shininess
+    // driving the fuzz), and everything else is diffuse.
+    fn build(&self) -> Arc<dyn Material> {
+        let transparent = self.dissolve < 1.0 || [4, 6, 7, 9].contains(&self.illumination);
+        let luminance = |c: Vec3| 0.2126 * c.r() + 0.7152 * c.g() + 0.0722 * c.b();
+        if transparent {
+            Arc::new(Dielectric::new(self.refraction_index))
+        } else if self.illumination == 3 || luminance(self.specular) > luminance(self.diffuse) {
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            Arc::new(Metallic::new(self.specular, fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, MeshError> {
+    let source = read(path)?;
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+    for (i, line) in source.lines().enumerate() {
+        let mut parser = LineParser::new(path, i + 1, line);
+        let keyword = match parser.keyword() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material.build());
+            }
+            current = Some((parser.name()?.to_string(), MtlMaterial::default()));
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None => return Err(parser.error(&format!("`{}` before any `newmtl`", keyword))),
+        };
+        match keyword {
+            "Kd" => material.diffuse = parser.vec3()?,
+            "Ks" => material.specular = parser.vec3()?,
+            "Ns" => material.shininess = parser.number()?,
+            "d" => material.dissolve = parser.number()?,
+            "Tr" => material.dissolve = 1.0 - parser.number::<f32>()?,
+            "Ni" => material.refraction_index = parser.number()?,
+            "illum" => material.illumination = parser.number()?,
+            // Other parameters have no counterpart in our materials.
+            _ => {}
+        }
+    }
+    if let Some((name, material)) = current.take() {
+        materials.insert(name, material.build());
+    }
+    Ok(materials)
+}
+
+fn read(path: &Path) -> Result<String, MeshError> {
+    fs::read_to_string(path).map_err(|error| MeshError::Io {
+        file: path.to_path_buf(),
+        error,
+    })
+}
+
+#[derive(Copy, Clone)]
+struct FaceCorner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+// Tokenizer for a single statement, tracking its position for error reporting.
+struct LineParser<'a> {
+    file: &'a Path,
+    line: usize,
+    tokens: SplitWhitespace<'a>,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(file: &'a Path, line: usize, text: &'a str) -> Self {
+        let text = match text.find('#') {
+            Some(i) => &text[..i],
+            None => text,
+        };
+        LineParser {
+            file,
+            line,
+            tokens: text.split_whitespace(),
+        }
+    }
+
+    fn error(&self, message: &str) -> MeshError {
+        MeshError::Parse {
+            file: self.file.to_path_buf(),
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+
+    fn keyword(&mut self) -> Option<&'a str> {
+        self.tokens.next()
+    }
+
+    fn remaining(&mut self) -> Vec<&'a str> {
+        self.tokens.by_ref().collect()
+    }
+
+    fn name(&mut self) -> Result<&'a str, MeshError> {
+        self.tokens.next().ok_or_else(|| self.error("missing name"))
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T, MeshError> {
+        match self.optional_number()? {
+            Some(value) => Ok(value),
+            None => Err(self.error("missing number")),
+        }
+    }
+
+    fn optional_number<T: FromStr>(&mut self) -> Result<Option<T>, MeshError> {
+        match self.tokens.next() {
+            Some(token) => match token.parse() {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => Err(self.error(&format!("invalid number `{}`", token))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, MeshError> {
+        Ok(Vec3::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    // Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`, with 1-based or negative (relative) indices.
+    fn face_corner(&self, token: &str, buffers: &MeshBuffers) -> Result<FaceCorner, MeshError> {
+        let mut parts = token.split('/');
+        let position = match parts.next() {
+            Some(index) => self.index(index, buffers.positions.len(), "vertex")?,
+            None => return Err(self.error(&format!("invalid face vertex `{}`", token))),
+        };
+        let uv = match parts.next() {
+            Some("") | None => None,
+            Some(index) => Some(self.index(index, buffers.uvs.len(), "texture coordinate")?),
+        };
+        let normal = match parts.next() {
+            Some("") | None => None,
+            Some(index) => Some(self.index(index, buffers.normals.len(), "normal")?),
+        };
+        if parts.next().is_some() {
+            return Err(self.error(&format!("invalid face vertex `{}`", token)));
+        }
+        Ok(FaceCorner {
+            position,
+            uv,
+            normal,
+        })
+    }
+
+    fn index(&self, token: &str, count: usize, kind: &str) -> Result<usize, MeshError> {
+        let index: i64 = token
+            .parse()
+            .map_err(|_| self.error(&format!("invalid {} index `{}`", kind, token)))?;
+        let resolved = if index > 0 {
+            index - 1
+        } else {
+            count as i64 + index
+        };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            Err(self.error(&format!("{} index {} out of range", kind, index)))
+        } else {
+            Ok(resolved as usize)
+        }
+    }
+}
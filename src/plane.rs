@@ -0,0 +1,67 @@
+use crate::aabb::Aabb;
+use crate::frame::Frame;
+use crate::hittable::{HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::sync::Arc;
+
+// Infinite plane through `point`, facing along `normal`. Surface coordinates are distances from
+// `point` along two directions in the plane, so textures repeat every unit unless scaled.
+//
+// It has no bounding box, and no finite area to sample as a light: an emissive plane is only
+// found by chance, so large disks or quads make better emitters.
+#[derive(Clone)]
+pub struct Plane {
+    frame: Frame,
+    point: Vec3,
+    normal: Vec3,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    // Panics if `normal` is zero.
+    pub fn new(point: Vec3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        let frame = Frame::new(point, normal);
+        Plane {
+            frame,
+            point,
+            normal: frame.axis(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let denominator = vec3::dot(&self.normal, &ray.direction);
+        // The ray is parallel to the plane.
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = vec3::dot(&self.normal, &(self.point - ray.origin)) / denominator;
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+        let point = ray.at(t);
+        let local = self.frame.point_to_local(point);
+        let mut hit = HitResult::with_uv(
+            t,
+            point,
+            self.normal,
+            (local.x, local.y),
+            Arc::clone(&self.material),
+        )
+        .with_derivatives(
+            self.frame.vector_to_world(Vec3::new(1.0, 0.0, 0.0)),
+            self.frame.vector_to_world(Vec3::new(0.0, 1.0, 0.0)),
+        );
+        hit.sampleable = false;
+        Some(hit)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        None
+    }
+}
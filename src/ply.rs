@@ -0,0 +1,455 @@
+use crate::material::{Lambertian, Material};
+use crate::mesh::{MeshBuffers, MeshError, MeshFace, TriangleMesh};
+use crate::vec3::Vec3;
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(ScalarType::I8),
+            "uchar" | "uint8" => Some(ScalarType::U8),
+            "short" | "int16" => Some(ScalarType::I16),
+            "ushort" | "uint16" => Some(ScalarType::U16),
+            "int" | "int32" => Some(ScalarType::I32),
+            "uint" | "uint32" => Some(ScalarType::U32),
+            "float" | "float32" => Some(ScalarType::F32),
+            "double" | "float64" => Some(ScalarType::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+
+    // Factor bringing color components of this type to [0, 1].
+    fn color_scale(self) -> f32 {
+        match self {
+            ScalarType::U8 => 1.0 / 255.0,
+            ScalarType::U16 => 1.0 / 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+enum PropertyKind {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+// Value of a property within an element record.
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn scalar(&self) -> f64 {
+        match self {
+            Value::Scalar(value) => *value,
+            Value::List(_) => 0.0,
+        }
+    }
+}
+
+// Loads an ASCII or binary PLY file. Vertex normals, texture coordinates and colors are used
+// when present. Meshes with vertex colors get a white diffuse material tinted by the colors
+// interpolated across each face, and `material` is used otherwise. Polygons are triangulated
+// as fans.
+pub fn load_ply(path: &Path, material: Arc<dyn Material>) -> Result<TriangleMesh, MeshError> {
+    let data = fs::read(path).map_err(|error| MeshError::Io {
+        file: path.to_path_buf(),
+        error,
+    })?;
+    let parse_error = |line: usize, message: String| MeshError::Parse {
+        file: path.to_path_buf(),
+        line,
+        message,
+    };
+    let invalid = |message: String| MeshError::Invalid {
+        file: path.to_path_buf(),
+        message,
+    };
+
+    let (encoding, elements, body_offset, header_lines) =
+        parse_header(&data).map_err(|(line, message)| match line {
+            Some(line) => parse_error(line, message),
+            None => invalid(message),
+        })?;
+
+    let mut body = match encoding {
+        Encoding::Ascii => {
+            let text = std::str::from_utf8(&data[body_offset..])
+                .map_err(|_| invalid("ASCII data is not valid text".to_string()))?;
+            Body::Ascii {
+                tokens: text
+                    .lines()
+                    .enumerate()
+                    .flat_map(|(i, line)| {
+                        line.split_whitespace()
+                            .map(move |token| (header_lines + i + 1, token))
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+                last_line: header_lines,
+            }
+        }
+        _ => Body::Binary {
+            data: &data[body_offset..],
+            offset: 0,
+            big_endian: encoding == Encoding::BinaryBigEndian,
+        },
+    };
+
+    let mut buffers = MeshBuffers::default();
+    let mut polygons: Vec<Vec<usize>> = Vec::new();
+    for element in elements.iter() {
+        let position = [
+            element.property(&["x"]),
+            element.property(&["y"]),
+            element.property(&["z"]),
+        ];
+        let normal = [
+            element.property(&["nx"]),
+            element.property(&["ny"]),
+            element.property(&["nz"]),
+        ];
+        let color = [
+            element.property(&["red", "diffuse_red", "r"]),
+            element.property(&["green", "diffuse_green", "g"]),
+            element.property(&["blue", "diffuse_blue", "b"]),
+        ];
+        let uv = [
+            element.property(&["u", "s", "texture_u", "texture_s"]),
+            element.property(&["v", "t", "texture_v", "texture_t"]),
+        ];
+        let indices = element.property(&["vertex_indices", "vertex_index"]);
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+        if is_vertex && position.iter().any(|p| p.is_none()) {
+            return Err(invalid("vertex element without x, y and z".to_string()));
+        }
+        if is_face && indices.is_none() {
+            return Err(invalid("face element without vertex indices".to_string()));
+        }
+
+        for _ in 0..element.count {
+            let record = body
+                .record(element)
+                .map_err(|message| body.error(path, message))?;
+
+            let vec3 = |properties: &[Option<usize>; 3], scale: [f32; 3]| {
+                Vec3::new(
+                    record[properties[0].unwrap()].scalar() as f32 * scale[0],
+                    record[properties[1].unwrap()].scalar() as f32 * scale[1],
+                    record[properties[2].unwrap()].scalar() as f32 * scale[2],
+                )
+            };
+            if is_vertex {
+                buffers.positions.push(vec3(&position, [1.0; 3]));
+                if normal.iter().all(|p| p.is_some()) {
+                    let normal = vec3(&normal, [1.0; 3]);
+                    if normal.squared_length() == 0.0 {
+                        return Err(body.error(path, "zero-length normal".to_string()));
+                    }
+                    buffers.normals.push(normal);
+                }
+                if color.iter().all(|p| p.is_some()) {
+                    let scale = |p: Option<usize>| match element.properties[p.unwrap()].kind {
+                        PropertyKind::Scalar(ty) => ty.color_scale(),
+                        PropertyKind::List { .. } => 1.0,
+                    };
+                    buffers.colors.push(vec3(
+                        &color,
+                        [scale(color[0]), scale(color[1]), scale(color[2])],
+                    ));
+                }
+                if uv.iter().all(|p| p.is_some()) {
+                    buffers.uvs.push((
+                        record[uv[0].unwrap()].scalar() as f32,
+                        record[uv[1].unwrap()].scalar() as f32,
+                    ));
+                }
+            } else if is_face {
+                match &record[indices.unwrap()] {
+                    Value::List(items) => {
+                        // Negative indices are left out of range, to be reported below.
+                        polygons.push(
+                            items
+                                .iter()
+                                .map(|&i| if i < 0.0 { usize::MAX } else { i as usize })
+                                .collect(),
+                        )
+                    }
+                    Value::Scalar(_) => {
+                        return Err(invalid("face vertex indices must be a list".to_string()))
+                    }
+                }
+            }
+        }
+    }
+
+    let vertex_count = buffers.positions.len();
+    let has_normals = buffers.normals.len() == vertex_count;
+    let has_uvs = buffers.uvs.len() == vertex_count;
+    let has_colors = buffers.colors.len() == vertex_count && vertex_count > 0;
+
+    let material: Arc<dyn Material> = if has_colors {
+        Arc::new(Lambertian::new(Vec3::from(1.0)))
+    } else {
+        material
+    };
+    let mut faces = Vec::new();
+    for (i, polygon) in polygons.iter().enumerate() {
+        if polygon.len() < 3 {
+            return Err(invalid(format!("face {} has fewer than 3 vertices", i)));
+        }
+        if let Some(&index) = polygon.iter().find(|&&index| index >= vertex_count) {
+            return Err(invalid(format!(
+                "face {} refers to missing vertex {}",
+                i, index
+            )));
+        }
+        for k in 1..polygon.len() - 1 {
+            let corners = [polygon[0], polygon[k], polygon[k + 1]];
+            faces.push(MeshFace {
+                positions: corners,
+                normals: if has_normals { Some(corners) } else { None },
+                uvs: if has_uvs { Some(corners) } else { None },
+                colors: if has_colors { Some(corners) } else { None },
+                material: 0,
+            });
+        }
+    }
+
+    Ok(TriangleMesh::new(buffers, faces, vec![material]))
+}
+
+type HeaderError = (Option<usize>, String);
+
+// Returns the encoding, the elements, the offset of the body and the number of header lines.
+fn parse_header(data: &[u8]) -> Result<(Encoding, Vec<Element>, usize, usize), HeaderError> {
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut line_number = 0;
+    loop {
+        let end = match data[offset..].iter().position(|&b| b == b'\n') {
+            Some(end) => offset + end,
+            None => return Err((None, "missing `end_header`".to_string())),
+        };
+        line_number += 1;
+        let line = String::from_utf8_lossy(&data[offset..end]);
+        offset = end + 1;
+
+        let error = |message: String| Err((Some(line_number), message));
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if line_number == 1 {
+            if tokens != ["ply"] {
+                return error("not a PLY file".to_string());
+            }
+            continue;
+        }
+        match tokens.as_slice() {
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::BinaryLittleEndian,
+                    "binary_big_endian" => Encoding::BinaryBigEndian,
+                    _ => return error(format!("unknown format `{}`", format)),
+                });
+            }
+            ["element", name, count] => match count.parse() {
+                Ok(count) => elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                }),
+                Err(_) => return error(format!("invalid element count `{}`", count)),
+            },
+            ["property", "list", count, item, name] => {
+                let kind = match (ScalarType::parse(count), ScalarType::parse(item)) {
+                    (Some(count), Some(item)) => PropertyKind::List { count, item },
+                    _ => return error(format!("unknown list type `{} {}`", count, item)),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                    }),
+                    None => return error("property before any element".to_string()),
+                }
+            }
+            ["property", ty, name] => {
+                let kind = match ScalarType::parse(ty) {
+                    Some(ty) => PropertyKind::Scalar(ty),
+                    None => return error(format!("unknown property type `{}`", ty)),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                    }),
+                    None => return error("property before any element".to_string()),
+                }
+            }
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return error(format!("invalid header line `{}`", line.trim())),
+        }
+    }
+
+    match encoding {
+        Some(encoding) => Ok((encoding, elements, offset, line_number)),
+        None => Err((None, "missing `format` in header".to_string())),
+    }
+}
+
+enum Body<'a> {
+    Ascii {
+        tokens: std::vec::IntoIter<(usize, &'a str)>,
+        last_line: usize,
+    },
+    Binary {
+        data: &'a [u8],
+        offset: usize,
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn record(&mut self, element: &Element) -> Result<Vec<Value>, String> {
+        let mut record = Vec::with_capacity(element.properties.len());
+        for property in element.properties.iter() {
+            record.push(match property.kind {
+                PropertyKind::Scalar(ty) => Value::Scalar(self.read(ty, &element.name)?),
+                PropertyKind::List { count, item } => {
+                    let n = self.read(count, &element.name)? as usize;
+                    // The count comes from the file, so it is only trusted as far as there is
+                    // data left to back it.
+                    let mut items = Vec::with_capacity(n.min(self.remaining(item)));
+                    for _ in 0..n {
+                        items.push(self.read(item, &element.name)?);
+                    }
+                    Value::List(items)
+                }
+            });
+        }
+        Ok(record)
+    }
+
+    // Upper bound on the number of values of type `ty` left to read.
+    fn remaining(&self, ty: ScalarType) -> usize {
+        match self {
+            Body::Ascii { tokens, .. } => tokens.len(),
+            Body::Binary { data, offset, .. } => (data.len() - offset) / ty.size(),
+        }
+    }
+
+    fn read(&mut self, ty: ScalarType, element: &str) -> Result<f64, String> {
+        match self {
+            Body::Ascii { tokens, last_line } => match tokens.next() {
+                Some((line, token)) => {
+                    *last_line = line;
+                    token
+                        .parse()
+                        .map_err(|_| format!("invalid number `{}` in element `{}`", token, element))
+                }
+                None => Err(format!("unexpected end of data in element `{}`", element)),
+            },
+            Body::Binary {
+                data,
+                offset,
+                big_endian,
+            } => {
+                let size = ty.size();
+                if *offset + size > data.len() {
+                    return Err(format!("unexpected end of data in element `{}`", element));
+                }
+                let mut bytes = [0u8; 8];
+                bytes[..size].copy_from_slice(&data[*offset..*offset + size]);
+                if *big_endian {
+                    bytes[..size].reverse();
+                }
+                *offset += size;
+                Ok(match ty {
+                    ScalarType::I8 => bytes[0] as i8 as f64,
+                    ScalarType::U8 => bytes[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::I32 => {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::U32 => {
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::F32 => {
+                        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::F64 => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+
+    // Errors in ASCII data point at the line of the last token read.
+    fn error(&self, path: &Path, message: String) -> MeshError {
+        match self {
+            Body::Ascii { last_line, .. } => MeshError::Parse {
+                file: path.to_path_buf(),
+                line: *last_line,
+                message,
+            },
+            Body::Binary { .. } => MeshError::Invalid {
+                file: path.to_path_buf(),
+                message,
+            },
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::aabb::{Aabb, FLAT_PADDING};
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::sync::Arc;
+
+// Parallelogram with a corner at `corner` and sides `u` and `v`. The normal is along u x v, and
+// (u, v) surface coordinates go from 0 to 1 along the sides.
+#[derive(Clone)]
+pub struct Quad {
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    // u x v over its squared length, to find the surface coordinates of points in the plane.
+    w: Vec3,
+    area: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    // Panics if the sides are parallel.
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = vec3::cross(&u, &v);
+        let area = n.length();
+        assert!(area > 0.0, "Quad sides must not be parallel.");
+        Quad {
+            corner,
+            u,
+            v,
+            normal: n / area,
+            w: n / (area * area),
+            area,
+            material,
+        }
+    }
+
+    // Axis-aligned rectangles facing +X, +Y or +Z, with surface coordinates laid out as seen
+    // from the side they face, with +Y (or -Z for xz_rect) up.
+    pub fn yz_rect(y: (f32, f32), z: (f32, f32), x: f32, material: Arc<dyn Material>) -> Self {
+        Quad::new(
+            Vec3::new(x, y.0, z.1),
+            Vec3::new(0.0, 0.0, z.0 - z.1),
+            Vec3::new(0.0, y.1 - y.0, 0.0),
+            material,
+        )
+    }
+
+    pub fn xz_rect(x: (f32, f32), z: (f32, f32), y: f32, material: Arc<dyn Material>) -> Self {
+        Quad::new(
+            Vec3::new(x.0, y, z.1),
+            Vec3::new(x.1 - x.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, z.0 - z.1),
+            material,
+        )
+    }
+
+    pub fn xy_rect(x: (f32, f32), y: (f32, f32), z: f32, material: Arc<dyn Material>) -> Self {
+        Quad::new(
+            Vec3::new(x.0, y.0, z),
+            Vec3::new(x.1 - x.0, 0.0, 0.0),
+            Vec3::new(0.0, y.1 - y.0, 0.0),
+            material,
+        )
+    }
+
+    // Copy facing the other way, with the same surface coordinates seen from the back.
+    pub fn flipped(&self) -> Self {
+        Quad::new(
+            self.corner + self.u,
+            -self.u,
+            self.v,
+            Arc::clone(&self.material),
+        )
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let denominator = vec3::dot(&self.normal, &ray.direction);
+        // The ray is parallel to the plane.
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = vec3::dot(&self.normal, &(self.corner - ray.origin)) / denominator;
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+        let point = ray.at(t);
+        let planar = point - self.corner;
+        let alpha = vec3::dot(&self.w, &vec3::cross(&planar, &self.v));
+        let beta = vec3::dot(&self.w, &vec3::cross(&self.u, &planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some(
+            HitResult::with_uv(
+                t,
+                point,
+                self.normal,
+                (alpha, beta),
+                Arc::clone(&self.material),
+            )
+            .with_derivatives(self.u, self.v),
+        )
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let bounds = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ]
+        .iter()
+        .fold(Aabb::empty(), |acc, corner| acc.grow(*corner));
+        Some(bounds.padded(FLAT_PADDING))
+    }
+
+    fn area(&self) -> f32 {
+        self.area
+    }
+
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        Some((self.corner + u.0 * self.u + u.1 * self.v, self.normal))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
+
+// Axis-aligned box made of six quads facing outwards.
+#[derive(Clone)]
+pub struct BoxShape {
+    min: Vec3,
+    max: Vec3,
+    faces: Vec<Quad>,
+}
+
+impl BoxShape {
+    // Panics unless `min` is below `max` along every axis.
+    pub fn new(min: Vec3, max: Vec3, material: Arc<dyn Material>) -> Self {
+        assert!(
+            min.x < max.x && min.y < max.y && min.z < max.z,
+            "Box corners must be ordered and apart."
+        );
+        let faces = vec![
+            Quad::yz_rect((min.y, max.y), (min.z, max.z), max.x, Arc::clone(&material)),
+            Quad::yz_rect((min.y, max.y), (min.z, max.z), min.x, Arc::clone(&material)).flipped(),
+            Quad::xz_rect((min.x, max.x), (min.z, max.z), max.y, Arc::clone(&material)),
+            Quad::xz_rect((min.x, max.x), (min.z, max.z), min.y, Arc::clone(&material)).flipped(),
+            Quad::xy_rect((min.x, max.x), (min.y, max.y), max.z, Arc::clone(&material)),
+            Quad::xy_rect((min.x, max.x), (min.y, max.y), min.z, material).flipped(),
+        ];
+        BoxShape { min, max, faces }
+    }
+}
+
+impl Hittable for BoxShape {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let mut result = None;
+        let mut closest_hit_distance = t_max;
+        for face in self.faces.iter() {
+            if let Some(hit) = face.hit(ray, t_min, closest_hit_distance) {
+                closest_hit_distance = hit.t;
+                result.replace(hit);
+            }
+        }
+        result
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    fn area(&self) -> f32 {
+        self.faces.iter().map(Quad::area).sum()
+    }
+
+    // Picks a face in proportion to its area, reusing the first coordinate within it.
+    fn sample_surface(&self, u: (f32, f32), time: f32) -> Option<(Vec3, Vec3)> {
+        let mut remaining = u.0 * self.area();
+        for (i, face) in self.faces.iter().enumerate() {
+            if remaining < face.area || i == self.faces.len() - 1 {
+                let u0 = (remaining / face.area).clamp(0.0, 1.0 - f32::EPSILON);
+                return face.sample_surface((u0, u.1), time);
+            }
+            remaining -= face.area;
+        }
+        None
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        self.faces.iter().flat_map(Quad::emitters).collect()
+    }
+}
@@ -0,0 +1,85 @@
+use crate::transform::Transform;
+use crate::vec3::{self, Vec3};
+
+// Unit quaternion representing a rotation.
+#[derive(Copy, Clone, Debug)]
+pub struct Quaternion {
+    pub w: f32,
+    pub v: Vec3,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Quaternion {
+            w: 1.0,
+            v: Vec3::default(),
+        }
+    }
+
+    // Same rotation as `Transform::rotate`. Panics if `axis` is zero.
+    pub fn from_axis_angle(axis: Vec3, degrees: f32) -> Self {
+        assert!(
+            axis.squared_length() > 0.0,
+            "Rotation axis must not be zero."
+        );
+        let (sin, cos) = (0.5 * degrees.to_radians()).sin_cos();
+        Quaternion {
+            w: cos,
+            v: sin * Vec3::unit_from(axis),
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        self.w * other.w + vec3::dot(&self.v, &other.v)
+    }
+
+    // Angle between the two rotations, in radians.
+    pub fn angle_to(&self, other: &Quaternion) -> f32 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    // Rotation turning at constant speed from `self` (t = 0) to `other` (t = 1), the short way
+    // round.
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Self {
+        // q and -q are the same rotation; the one closer to `self` gives the shorter arc.
+        let (cos, other) = match self.dot(other) {
+            cos if cos < 0.0 => (-cos, other.scaled(-1.0)),
+            cos => (cos, *other),
+        };
+        let (a, b) = if cos > 0.9995 {
+            // Nearly the same rotation, where sin(theta) vanishes: interpolate linearly.
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        let q = Quaternion {
+            w: a * self.w + b * other.w,
+            v: a * self.v + b * other.v,
+        };
+        q.scaled(1.0 / q.dot(&q).sqrt())
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        let sin = self.v.length();
+        if sin == 0.0 {
+            return Transform::identity();
+        }
+        let angle = 2.0 * sin.atan2(self.w);
+        Transform::rotate(self.v / sin, angle.to_degrees())
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Quaternion {
+            w: factor * self.w,
+            v: factor * self.v,
+        }
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
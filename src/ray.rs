@@ -5,6 +5,9 @@ pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
     pub time: f32,
+    // Widening of the ray footprint per unit of distance (in radians), used to filter texture
+    // lookups. It is 0 for rays that don't track their footprint.
+    pub spread: f32,
 }
 
 impl Ray {
@@ -17,9 +20,15 @@ impl Ray {
             origin,
             direction,
             time,
+            spread: 0.0,
         }
     }
 
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + t * self.direction
     }
@@ -0,0 +1,100 @@
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::rng::{self, random};
+use crate::tile;
+use crate::tonemap::ToneMapping;
+use crate::vec3::Vec3;
+use crate::world::World;
+
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: usize,
+    // Bounces after which paths are cut off. Most paths are ended earlier by Russian roulette.
+    pub max_depth: i32,
+    pub seed: u64,
+    pub tile_size: usize,
+    // Used when saving to low dynamic range formats; the framebuffer itself stays linear.
+    pub tone_mapping: ToneMapping,
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: 400,
+            height: 200,
+            samples_per_pixel: 50,
+            max_depth: 50,
+            seed: 0,
+            tile_size: 16,
+            tone_mapping: ToneMapping::default(),
+        }
+    }
+}
+
+pub struct Renderer {
+    settings: RenderSettings,
+    camera: Camera,
+    world: World,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings, camera: Camera, world: World) -> Self {
+        Renderer {
+            settings,
+            camera,
+            world,
+        }
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    // Renders on the current rayon thread pool. Each tile reseeds its thread's generator,
+    // so the output is the same whatever the number of threads.
+    pub fn render(&self) -> Framebuffer {
+        let settings = &self.settings;
+        let rendered_tiles: Vec<_> =
+            tile::tiles(settings.width, settings.height, settings.tile_size)
+                .into_par_iter()
+                .map(|tile| {
+                    rng::seed_thread_rng(tile.seed(settings.seed));
+                    let colors: Vec<Vec3> = tile.pixels().map(|(x, y)| self.sample(x, y)).collect();
+                    (tile, colors)
+                })
+                .collect();
+
+        let mut framebuffer = Framebuffer::new(settings.width, settings.height);
+        for (tile, colors) in rendered_tiles {
+            for ((x, y), color) in tile.pixels().zip(colors) {
+                framebuffer.set(x, y, color);
+            }
+        }
+        framebuffer
+    }
+
+    // Average radiance through pixel (x, y), with y growing downwards.
+    fn sample(&self, x: usize, y: usize) -> Vec3 {
+        let settings = &self.settings;
+        let row = settings.height - 1 - y;
+        let spread = self.camera.pixel_spread(settings.height);
+        let mut color = Vec3::default();
+        for _ in 0..settings.samples_per_pixel {
+            let u = (x as f32 + random::<f32>()) / settings.width as f32;
+            let v = (row as f32 + random::<f32>()) / settings.height as f32;
+            let ray = self.camera.ray_at(u, v).with_spread(spread);
+            color += self.world.color(&ray, settings.max_depth);
+        }
+        color / settings.samples_per_pixel as f32
+    }
+}
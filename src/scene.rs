@@ -0,0 +1,1103 @@
+use crate::animation::{Animated, AnimatedTransform, Keyframe};
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::cone::Cone;
+use crate::cylinder::Cylinder;
+use crate::disk::Disk;
+use crate::environment::EnvironmentLight;
+use crate::gltf;
+use crate::hittable::Hittable;
+use crate::image;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metallic};
+use crate::mesh::{self, MeshError};
+use crate::mipmap::{TextureFilter, WrapMode};
+use crate::plane::Plane;
+use crate::quad::{BoxShape, Quad};
+use crate::quaternion::Quaternion;
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{
+    CellsTexture, CheckerTexture, CloudsTexture, ConstantTexture, ImageTexture, MarbleTexture,
+    Texture, WoodTexture,
+};
+use crate::torus::Torus;
+use crate::transform::{Transform, Transformed};
+use crate::triangle::Triangle;
+use crate::vec3::Vec3;
+use crate::world::World;
+
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+// Declarative scene, as read from a JSON file:
+//
+// {
+//   "camera": { "look_from": [13, 2, 3], "look_at": [0, 0, 0], "vertical_fov": 20,
+//               "aperture": 0.1, "focus_distance": 10, "time_range": [0, 1] },
+//   "textures": { "checks": { "checker": { "odd": [0, 0, 0], "even": [1, 1, 1], "scale": 10 } } },
+//   "materials": { "glass": { "dielectric": { "refraction_index": 1.5 } },
+//                  "floor": { "lambertian": { "albedo": "checks" } } },
+//   "objects": [ { "sphere": { "center": [0, 1, 0], "radius": 1, "material": "glass" } } ]
+// }
+//
+// Camera fields mirror `Camera::new`; `up` defaults to +Y, `time_range` to [0, 0] and
+// `aspect_ratio` to the one of the image being rendered. Albedos are either a color or the
+// name of a texture. The optional `background` is `{ "color": [r, g, b] }`,
+// `{ "gradient": { "bottom": [r, g, b], "top": [r, g, b] } }`, `{ "environment": "texture" }`
+// or `{ "environment_light": { "file": "sky.hdr", "rotation": 90, "intensity": 1 } }`, and
+// defaults to a light blue sky.
+//
+// Any object can be moved, rotated or scaled by wrapping it in an instance:
+// `{ "instance": { "object": { "mesh": { "file": "bunny.obj" } }, "transform": [ { "scale": 2 },
+// { "rotate": { "axis": [0, 1, 0], "degrees": 45 } }, { "translate": [1, 0, 0] } ] } }`, or
+// animated for motion blur over the camera's `time_range`:
+// `{ "animated": { "object": { ... }, "keyframes": [ { "time": 0, "translate": [0, 0, 0] },
+// { "time": 1, "translate": [0, 1, 0], "rotate": { "axis": [0, 0, 1], "degrees": 90 } } ] } }`.
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    // Malformed JSON, or a value of the wrong shape: `path` is the offending field
+    // (e.g. `objects[2].radius`) and the position is 1-based.
+    Parse {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // Well-formed but inconsistent description (e.g. a reference to an unknown material).
+    Invalid {
+        path: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{}", e),
+            SceneError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "line {}, column {}: ", line, column)?;
+                // Syntax errors happen outside of any known field.
+                let path = path.trim_end_matches('?').trim_end_matches('.');
+                if !path.is_empty() {
+                    write!(f, "{}: ", path)?;
+                }
+                write!(f, "{}", message)
+            }
+            SceneError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SceneError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+// Names accepted by `Scene::builtin`.
+pub const BUILTIN_SCENES: &[&str] = &["random"];
+
+impl Scene {
+    pub fn builtin(name: &str, aspect_ratio: f32) -> Option<Self> {
+        match name {
+            "random" => Some(Scene::random(aspect_ratio)),
+            _ => None,
+        }
+    }
+
+    // The final scene of "Ray Tracing in One Weekend", with bouncing diffuse spheres.
+    pub fn random(aspect_ratio: f32) -> Self {
+        let camera = Camera::new(
+            Vec3::new(13.0, 2.0, 3.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            20.0,
+            aspect_ratio,
+            0.1,
+            10.0,
+            (0.0, 1.0),
+        );
+        Scene {
+            camera,
+            world: World::random(),
+        }
+    }
+
+    // Files referenced by the scene are resolved relative to its directory.
+    pub fn load(path: &Path, aspect_ratio: f32) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path)?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        Scene::from_json(&source, directory, aspect_ratio)
+    }
+
+    // Imports a glTF file with its first camera, or one framing the whole scene if it has none.
+    pub fn from_gltf(path: &Path, aspect_ratio: f32) -> Result<Self, MeshError> {
+        let gltf = gltf::load_gltf(path, Arc::new(Lambertian::new(Vec3::from(0.5))))?;
+        let camera = match gltf.camera(aspect_ratio) {
+            Some(camera) => camera,
+            None => {
+                let (center, radius) = match gltf.mesh.bounding_box(0.0, 0.0) {
+                    Some(bounds) => (bounds.centroid(), 0.5 * bounds.extent().length()),
+                    None => (Vec3::default(), 1.0),
+                };
+                let vertical_fov: f32 = 40.0;
+                let distance = radius / (0.5 * vertical_fov.to_radians()).sin();
+                Camera::new(
+                    center + Vec3::new(0.0, 0.0, distance),
+                    center,
+                    Vec3::new(0.0, 1.0, 0.0),
+                    vertical_fov,
+                    aspect_ratio,
+                    0.0,
+                    distance,
+                    (0.0, 0.0),
+                )
+            }
+        };
+        let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(gltf.mesh)];
+        objects.extend(gltf.lights);
+        Ok(Scene {
+            camera,
+            world: World::new(objects),
+        })
+    }
+
+    pub fn from_json(
+        source: &str,
+        directory: &Path,
+        aspect_ratio: f32,
+    ) -> Result<Self, SceneError> {
+        let mut deserializer = serde_json::Deserializer::from_str(source);
+        let description: SceneDescription = serde_path_to_error::deserialize(&mut deserializer)
+            .map_err(|e| {
+                let path = e.path().to_string();
+                let inner = e.into_inner();
+                SceneError::Parse {
+                    path,
+                    line: inner.line(),
+                    column: inner.column(),
+                    message: strip_position(&inner.to_string()),
+                }
+            })?;
+        description.build(directory, aspect_ratio)
+    }
+}
+
+// serde_json appends " at line L column C" to its messages, which is reported separately.
+fn strip_position(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message.to_string(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    camera: CameraDescription,
+    #[serde(default)]
+    background: Option<BackgroundDescription>,
+    #[serde(default)]
+    textures: HashMap<String, TextureDescription>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    objects: Vec<ObjectDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    look_from: [f32; 3],
+    look_at: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    vertical_fov: f32,
+    aspect_ratio: Option<f32>,
+    #[serde(default)]
+    aperture: f32,
+    focus_distance: f32,
+    #[serde(default)]
+    time_range: (f32, f32),
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDescription {
+    Color([f32; 3]),
+    Gradient {
+        bottom: [f32; 3],
+        top: [f32; 3],
+    },
+    // Name of a texture, usually an equirectangular image.
+    Environment(String),
+    // Equirectangular HDR or EXR image, turned `rotation` degrees around +Y and scaled by
+    // `intensity`.
+    EnvironmentLight {
+        file: String,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDescription {
+    Checker {
+        odd: [f32; 3],
+        even: [f32; 3],
+        scale: f32,
+    },
+    // PNG, JPEG or Radiance HDR file, resolved relative to the scene. Lookups repeat the image
+    // and are filtered trilinearly by default.
+    Image {
+        file: String,
+        #[serde(default)]
+        wrap: WrapDescription,
+        #[serde(default)]
+        filter: FilterDescription,
+    },
+    // Procedural textures; `scale` is the frequency of the pattern and `seed` picks its noise.
+    Marble {
+        #[serde(default)]
+        seed: u64,
+        scale: f32,
+        base: [f32; 3],
+        vein: [f32; 3],
+    },
+    Wood {
+        #[serde(default)]
+        seed: u64,
+        scale: f32,
+        light: [f32; 3],
+        dark: [f32; 3],
+    },
+    Clouds {
+        #[serde(default)]
+        seed: u64,
+        scale: f32,
+        sky: [f32; 3],
+        cloud: [f32; 3],
+        #[serde(default = "default_coverage")]
+        coverage: f32,
+    },
+    Cells {
+        #[serde(default)]
+        seed: u64,
+        scale: f32,
+        cell: [f32; 3],
+        border: [f32; 3],
+    },
+}
+
+fn default_coverage() -> f32 {
+    0.5
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum WrapDescription {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum FilterDescription {
+    Nearest,
+    Bilinear,
+    #[default]
+    Trilinear,
+    Ewa,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "expected a color or a texture name")]
+enum ColorDescription {
+    Color([f32; 3]),
+    Texture(String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+    Lambertian { albedo: ColorDescription },
+    Metallic { albedo: ColorDescription, fuzz: f32 },
+    Dielectric { refraction_index: f32 },
+    // Radiance, which may go above 1.
+    DiffuseLight { emit: ColorDescription },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDescription {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    MovingSphere {
+        center_range: ([f32; 3], [f32; 3]),
+        time_range: (f32, f32),
+        radius: f32,
+        material: String,
+    },
+    Triangle {
+        vertices: [[f32; 3]; 3],
+        normals: Option<[[f32; 3]; 3]>,
+        uvs: Option<[(f32, f32); 3]>,
+        material: String,
+    },
+    // Parallelogram facing along u x v.
+    Quad {
+        corner: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    // Axis-aligned box between two opposite corners.
+    Box {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: String,
+    },
+    // Flat disk facing along `normal`.
+    Disk {
+        center: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    // Capped cylinder between the centers of its caps.
+    Cylinder {
+        base: [f32; 3],
+        top: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    // Cone closed by its base, with `radius` at `base`.
+    Cone {
+        base: [f32; 3],
+        apex: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    // Ring torus around `axis`: a tube of `minor_radius` around a circle of `major_radius`.
+    Torus {
+        center: [f32; 3],
+        axis: [f32; 3],
+        major_radius: f32,
+        minor_radius: f32,
+        material: String,
+    },
+    // Infinite plane facing along `normal`.
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        material: String,
+    },
+    // Another object placed by transforms applied in order, e.g. a scale, then a rotation, then
+    // a translation. Instances of the same mesh file share its geometry.
+    Instance {
+        object: Box<ObjectDescription>,
+        transform: Vec<TransformDescription>,
+    },
+    // Another object moving through keyframed poses, which rays see at their time for motion
+    // blur.
+    Animated {
+        object: Box<ObjectDescription>,
+        keyframes: Vec<KeyframeDescription>,
+    },
+    // OBJ, PLY, STL or glTF file. `material` applies to faces without one from the file (OBJ
+    // materials and PLY vertex colors), and defaults to a grey diffuse.
+    Mesh {
+        file: String,
+        material: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDescription {
+    Translate([f32; 3]),
+    Scale(ScaleDescription),
+    Rotate(RotationDescription),
+    // Rows of a 4x4 matrix acting on column vectors.
+    Matrix([[f32; 4]; 4]),
+}
+
+// Counterclockwise looking down the axis.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RotationDescription {
+    axis: [f32; 3],
+    degrees: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "expected a factor or one factor per axis")]
+enum ScaleDescription {
+    Uniform(f32),
+    Axes([f32; 3]),
+}
+
+// Pose at `time`, scaled, then rotated, then translated. Rotations between keyframes take the
+// short way round.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDescription {
+    time: f32,
+    #[serde(default)]
+    translate: [f32; 3],
+    #[serde(default)]
+    rotate: Option<RotationDescription>,
+    #[serde(default)]
+    scale: Option<ScaleDescription>,
+}
+
+// Meshes already loaded for instances, by file and material.
+type MeshCache = HashMap<(String, Option<String>), Arc<dyn Hittable>>;
+
+fn invalid<T>(path: String, message: &str) -> Result<T, SceneError> {
+    Err(SceneError::Invalid {
+        path,
+        message: message.to_string(),
+    })
+}
+
+impl SceneDescription {
+    fn build(self, directory: &Path, aspect_ratio: f32) -> Result<Scene, SceneError> {
+        let camera = self.camera.build(aspect_ratio)?;
+
+        let mut textures = HashMap::with_capacity(self.textures.len());
+        for (name, description) in self.textures {
+            let texture = description.build(format!("textures.{}", name), directory)?;
+            textures.insert(name, texture);
+        }
+
+        let mut materials = HashMap::with_capacity(self.materials.len());
+        for (name, description) in self.materials {
+            let material = description.build(format!("materials.{}", name), &textures)?;
+            materials.insert(name, material);
+        }
+
+        let background = match self.background {
+            Some(description) => description.build(directory, &textures)?,
+            None => Background::default(),
+        };
+
+        let mut world = World::default().with_background(background);
+        let mut meshes = MeshCache::new();
+        for (i, description) in self.objects.into_iter().enumerate() {
+            world.add(description.build(
+                format!("objects[{}]", i),
+                directory,
+                &materials,
+                &mut meshes,
+            )?);
+        }
+
+        Ok(Scene { camera, world })
+    }
+}
+
+impl CameraDescription {
+    fn build(self, aspect_ratio: f32) -> Result<Camera, SceneError> {
+        let aspect_ratio = self.aspect_ratio.unwrap_or(aspect_ratio);
+        if !(self.vertical_fov > 0.0 && self.vertical_fov < 180.0) {
+            return invalid(
+                "camera.vertical_fov".to_string(),
+                "must be between 0 and 180 degrees",
+            );
+        }
+        if aspect_ratio <= 0.0 {
+            return invalid("camera.aspect_ratio".to_string(), "must be positive");
+        }
+        if self.aperture < 0.0 {
+            return invalid("camera.aperture".to_string(), "must not be negative");
+        }
+        if self.focus_distance <= 0.0 {
+            return invalid("camera.focus_distance".to_string(), "must be positive");
+        }
+        if self.time_range.1 < self.time_range.0 {
+            return invalid(
+                "camera.time_range".to_string(),
+                "must not end before it starts",
+            );
+        }
+        if self.look_from == self.look_at {
+            return invalid("camera.look_at".to_string(), "must differ from look_from");
+        }
+        Ok(Camera::new(
+            to_vec3(self.look_from),
+            to_vec3(self.look_at),
+            to_vec3(self.up),
+            self.vertical_fov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_distance,
+            self.time_range,
+        ))
+    }
+}
+
+impl BackgroundDescription {
+    fn build(
+        self,
+        directory: &Path,
+        textures: &HashMap<String, Arc<dyn Texture>>,
+    ) -> Result<Background, SceneError> {
+        Ok(match self {
+            BackgroundDescription::Color(color) => Background::Color(to_vec3(color)),
+            BackgroundDescription::Gradient { bottom, top } => Background::Gradient {
+                bottom: to_vec3(bottom),
+                top: to_vec3(top),
+            },
+            BackgroundDescription::Environment(name) => match textures.get(&name) {
+                Some(texture) => Background::Environment(Arc::clone(texture)),
+                None => {
+                    return invalid(
+                        "background.environment".to_string(),
+                        &format!("unknown texture `{}`", name),
+                    )
+                }
+            },
+            BackgroundDescription::EnvironmentLight {
+                file,
+                rotation,
+                intensity,
+            } => {
+                if intensity < 0.0 {
+                    return invalid(
+                        "background.environment_light.intensity".to_string(),
+                        "must not be negative",
+                    );
+                }
+                match EnvironmentLight::load(&directory.join(&file), rotation, intensity) {
+                    Ok(light) => Background::EnvironmentLight(light),
+                    Err(e) => {
+                        return invalid(
+                            "background.environment_light.file".to_string(),
+                            &format!("{}: {}", file, e),
+                        )
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl TextureDescription {
+    fn build(self, path: String, directory: &Path) -> Result<Arc<dyn Texture>, SceneError> {
+        Ok(match self {
+            TextureDescription::Checker { odd, even, scale } => {
+                if scale <= 0.0 {
+                    return invalid(path + ".checker.scale", "must be positive");
+                }
+                Arc::new(CheckerTexture::new(
+                    Arc::new(ConstantTexture::new(to_vec3(odd))),
+                    Arc::new(ConstantTexture::new(to_vec3(even))),
+                    scale,
+                ))
+            }
+            TextureDescription::Image { file, wrap, filter } => {
+                let path = path + ".image.file";
+                let image = match image::load_image(&directory.join(&file)) {
+                    Ok(image) => image,
+                    Err(e) => return invalid(path, &format!("{}: {}", file, e)),
+                };
+                if image.width() == 0 || image.height() == 0 {
+                    return invalid(path, &format!("{}: empty image", file));
+                }
+                let wrap = match wrap {
+                    WrapDescription::Repeat => WrapMode::Repeat,
+                    WrapDescription::Clamp => WrapMode::Clamp,
+                    WrapDescription::Mirror => WrapMode::Mirror,
+                };
+                let filter = match filter {
+                    FilterDescription::Nearest => TextureFilter::Nearest,
+                    FilterDescription::Bilinear => TextureFilter::Bilinear,
+                    FilterDescription::Trilinear => TextureFilter::Trilinear,
+                    FilterDescription::Ewa => TextureFilter::Ewa,
+                };
+                Arc::new(ImageTexture::new(image, wrap, filter))
+            }
+            TextureDescription::Marble {
+                seed,
+                scale,
+                base,
+                vein,
+            } => {
+                if scale <= 0.0 {
+                    return invalid(path + ".marble.scale", "must be positive");
+                }
+                Arc::new(MarbleTexture::new(
+                    seed,
+                    scale,
+                    to_vec3(base),
+                    to_vec3(vein),
+                ))
+            }
+            TextureDescription::Wood {
+                seed,
+                scale,
+                light,
+                dark,
+            } => {
+                if scale <= 0.0 {
+                    return invalid(path + ".wood.scale", "must be positive");
+                }
+                Arc::new(WoodTexture::new(seed, scale, to_vec3(light), to_vec3(dark)))
+            }
+            TextureDescription::Clouds {
+                seed,
+                scale,
+                sky,
+                cloud,
+                coverage,
+            } => {
+                if scale <= 0.0 {
+                    return invalid(path + ".clouds.scale", "must be positive");
+                }
+                if !(0.0..=1.0).contains(&coverage) {
+                    return invalid(path + ".clouds.coverage", "must be between 0 and 1");
+                }
+                Arc::new(CloudsTexture::new(
+                    seed,
+                    scale,
+                    to_vec3(sky),
+                    to_vec3(cloud),
+                    coverage,
+                ))
+            }
+            TextureDescription::Cells {
+                seed,
+                scale,
+                cell,
+                border,
+            } => {
+                if scale <= 0.0 {
+                    return invalid(path + ".cells.scale", "must be positive");
+                }
+                Arc::new(CellsTexture::new(
+                    seed,
+                    scale,
+                    to_vec3(cell),
+                    to_vec3(border),
+                ))
+            }
+        })
+    }
+}
+
+impl ColorDescription {
+    fn build(
+        self,
+        path: String,
+        textures: &HashMap<String, Arc<dyn Texture>>,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        match self {
+            ColorDescription::Color(color) => Ok(Arc::new(ConstantTexture::new(to_vec3(color)))),
+            ColorDescription::Texture(name) => match textures.get(&name) {
+                Some(texture) => Ok(Arc::clone(texture)),
+                None => invalid(path, &format!("unknown texture `{}`", name)),
+            },
+        }
+    }
+}
+
+impl MaterialDescription {
+    fn build(
+        self,
+        path: String,
+        textures: &HashMap<String, Arc<dyn Texture>>,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        Ok(match self {
+            MaterialDescription::Lambertian { albedo } => {
+                let albedo = albedo.build(path + ".lambertian.albedo", textures)?;
+                Arc::new(Lambertian::textured(albedo))
+            }
+            MaterialDescription::Metallic { albedo, fuzz } => {
+                if !(0.0..=1.0).contains(&fuzz) {
+                    return invalid(path + ".metallic.fuzz", "must be between 0 and 1");
+                }
+                let albedo = albedo.build(path + ".metallic.albedo", textures)?;
+                Arc::new(Metallic::textured(albedo, fuzz))
+            }
+            MaterialDescription::Dielectric { refraction_index } => {
+                if refraction_index <= 0.0 {
+                    return invalid(path + ".dielectric.refraction_index", "must be positive");
+                }
+                Arc::new(Dielectric::new(refraction_index))
+            }
+            MaterialDescription::DiffuseLight { emit } => {
+                if let ColorDescription::Color(color) = emit {
+                    if color.iter().any(|&c| c < 0.0) {
+                        return invalid(path + ".diffuse_light.emit", "must not be negative");
+                    }
+                }
+                let emit = emit.build(path + ".diffuse_light.emit", textures)?;
+                Arc::new(DiffuseLight::textured(emit))
+            }
+        })
+    }
+}
+
+impl ObjectDescription {
+    fn build(
+        self,
+        path: String,
+        directory: &Path,
+        materials: &HashMap<String, Arc<dyn Material>>,
+        meshes: &mut MeshCache,
+    ) -> Result<Box<dyn Hittable>, SceneError> {
+        Ok(match self {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                let path = path + ".sphere";
+                if radius <= 0.0 {
+                    return invalid(path + ".radius", "must be positive");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Sphere::new(to_vec3(center), radius, material))
+            }
+            ObjectDescription::MovingSphere {
+                center_range,
+                time_range,
+                radius,
+                material,
+            } => {
+                let path = path + ".moving_sphere";
+                if radius <= 0.0 {
+                    return invalid(path + ".radius", "must be positive");
+                }
+                if time_range.1 <= time_range.0 {
+                    return invalid(path + ".time_range", "must end after it starts");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(MovingSphere::new(
+                    (to_vec3(center_range.0), to_vec3(center_range.1)),
+                    time_range,
+                    radius,
+                    material,
+                ))
+            }
+            ObjectDescription::Triangle {
+                vertices,
+                normals,
+                uvs,
+                material,
+            } => {
+                let path = path + ".triangle";
+                let vertices = [
+                    to_vec3(vertices[0]),
+                    to_vec3(vertices[1]),
+                    to_vec3(vertices[2]),
+                ];
+                let area = 0.5
+                    * (vertices[1] - vertices[0])
+                        .cross(&(vertices[2] - vertices[0]))
+                        .length();
+                if area == 0.0 {
+                    return invalid(path + ".vertices", "must not be collinear");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                let mut triangle = Triangle::new(vertices, material);
+                if let Some(normals) = normals {
+                    triangle = triangle.with_normals([
+                        to_vec3(normals[0]),
+                        to_vec3(normals[1]),
+                        to_vec3(normals[2]),
+                    ]);
+                }
+                if let Some(uvs) = uvs {
+                    triangle = triangle.with_uvs(uvs);
+                }
+                Box::new(triangle)
+            }
+            ObjectDescription::Quad {
+                corner,
+                u,
+                v,
+                material,
+            } => {
+                let path = path + ".quad";
+                if to_vec3(u).cross(&to_vec3(v)).length() == 0.0 {
+                    return invalid(path + ".v", "must not be parallel to u");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Quad::new(to_vec3(corner), to_vec3(u), to_vec3(v), material))
+            }
+            ObjectDescription::Box { min, max, material } => {
+                let path = path + ".box";
+                if (0..3).any(|i| min[i] >= max[i]) {
+                    return invalid(path + ".max", "must be above min along every axis");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(BoxShape::new(to_vec3(min), to_vec3(max), material))
+            }
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                let path = path + ".disk";
+                if radius <= 0.0 {
+                    return invalid(path + ".radius", "must be positive");
+                }
+                if to_vec3(normal).length() == 0.0 {
+                    return invalid(path + ".normal", "must not be zero");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Disk::new(
+                    to_vec3(center),
+                    to_vec3(normal),
+                    radius,
+                    material,
+                ))
+            }
+            ObjectDescription::Cylinder {
+                base,
+                top,
+                radius,
+                material,
+            } => {
+                let path = path + ".cylinder";
+                if radius <= 0.0 {
+                    return invalid(path + ".radius", "must be positive");
+                }
+                if (to_vec3(top) - to_vec3(base)).length() == 0.0 {
+                    return invalid(path + ".top", "must not be the base");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Cylinder::new(to_vec3(base), to_vec3(top), radius, material))
+            }
+            ObjectDescription::Cone {
+                base,
+                apex,
+                radius,
+                material,
+            } => {
+                let path = path + ".cone";
+                if radius <= 0.0 {
+                    return invalid(path + ".radius", "must be positive");
+                }
+                if (to_vec3(apex) - to_vec3(base)).length() == 0.0 {
+                    return invalid(path + ".apex", "must not be the base");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Cone::new(to_vec3(base), to_vec3(apex), radius, material))
+            }
+            ObjectDescription::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                material,
+            } => {
+                let path = path + ".torus";
+                if minor_radius <= 0.0 {
+                    return invalid(path + ".minor_radius", "must be positive");
+                }
+                if minor_radius >= major_radius {
+                    return invalid(path + ".minor_radius", "must be below major_radius");
+                }
+                if to_vec3(axis).length() == 0.0 {
+                    return invalid(path + ".axis", "must not be zero");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Torus::new(
+                    to_vec3(center),
+                    to_vec3(axis),
+                    major_radius,
+                    minor_radius,
+                    material,
+                ))
+            }
+            ObjectDescription::Plane {
+                point,
+                normal,
+                material,
+            } => {
+                let path = path + ".plane";
+                if to_vec3(normal).length() == 0.0 {
+                    return invalid(path + ".normal", "must not be zero");
+                }
+                let material = lookup_material(materials, &material, &path)?;
+                Box::new(Plane::new(to_vec3(point), to_vec3(normal), material))
+            }
+            ObjectDescription::Instance { object, transform } => {
+                let path = path + ".instance";
+                let mut combined = Transform::identity();
+                for (i, step) in transform.into_iter().enumerate() {
+                    combined = step.build(format!("{}.transform[{}]", path, i))? * combined;
+                }
+                let object = object.build_shared(path + ".object", directory, materials, meshes)?;
+                Box::new(Transformed::new(object, combined))
+            }
+            ObjectDescription::Animated { object, keyframes } => {
+                let path = path + ".animated";
+                if keyframes.is_empty() {
+                    return invalid(path + ".keyframes", "must not be empty");
+                }
+                let keyframes = keyframes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, keyframe)| keyframe.build(format!("{}.keyframes[{}]", path, i)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let object = object.build_shared(path + ".object", directory, materials, meshes)?;
+                Box::new(Animated::new(object, AnimatedTransform::new(keyframes)))
+            }
+            ObjectDescription::Mesh { file, material } => {
+                let path = path + ".mesh";
+                let material = match material {
+                    Some(material) => lookup_material(materials, &material, &path)?,
+                    None => Arc::new(Lambertian::new(Vec3::from(0.5))),
+                };
+                match mesh::load_mesh(&directory.join(file), material) {
+                    Ok(mesh) => Box::new(mesh),
+                    Err(e) => return invalid(path + ".file", &e.to_string()),
+                }
+            }
+        })
+    }
+
+    // Like `build`, but a mesh file is loaded only once however many instances place it.
+    fn build_shared(
+        self,
+        path: String,
+        directory: &Path,
+        materials: &HashMap<String, Arc<dyn Material>>,
+        meshes: &mut MeshCache,
+    ) -> Result<Arc<dyn Hittable>, SceneError> {
+        let key = match &self {
+            ObjectDescription::Mesh { file, material } => Some((file.clone(), material.clone())),
+            _ => None,
+        };
+        if let Some(mesh) = key.as_ref().and_then(|key| meshes.get(key)) {
+            return Ok(Arc::clone(mesh));
+        }
+        let object: Arc<dyn Hittable> = Arc::from(self.build(path, directory, materials, meshes)?);
+        if let Some(key) = key {
+            meshes.insert(key, Arc::clone(&object));
+        }
+        Ok(object)
+    }
+}
+
+impl TransformDescription {
+    fn build(self, path: String) -> Result<Transform, SceneError> {
+        Ok(match self {
+            TransformDescription::Translate(offset) => Transform::translate(to_vec3(offset)),
+            TransformDescription::Scale(scale) => {
+                let factors = scale.factors();
+                if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+                    return invalid(path + ".scale", "must not be zero");
+                }
+                Transform::scale(factors)
+            }
+            TransformDescription::Rotate(rotation) => {
+                if to_vec3(rotation.axis).length() == 0.0 {
+                    return invalid(path + ".rotate.axis", "must not be zero");
+                }
+                Transform::rotate(to_vec3(rotation.axis), rotation.degrees)
+            }
+            TransformDescription::Matrix(matrix) => match Transform::from_matrix(matrix) {
+                Some(transform) => transform,
+                None => return invalid(path + ".matrix", "must be invertible"),
+            },
+        })
+    }
+}
+
+impl ScaleDescription {
+    fn factors(self) -> Vec3 {
+        match self {
+            ScaleDescription::Uniform(factor) => Vec3::from(factor),
+            ScaleDescription::Axes(factors) => to_vec3(factors),
+        }
+    }
+}
+
+impl KeyframeDescription {
+    fn build(self, path: String) -> Result<Keyframe, SceneError> {
+        let mut keyframe = Keyframe::at(self.time).with_translation(to_vec3(self.translate));
+        if let Some(rotation) = self.rotate {
+            if to_vec3(rotation.axis).length() == 0.0 {
+                return invalid(path + ".rotate.axis", "must not be zero");
+            }
+            keyframe = keyframe.with_rotation(Quaternion::from_axis_angle(
+                to_vec3(rotation.axis),
+                rotation.degrees,
+            ));
+        }
+        if let Some(scale) = self.scale {
+            let factors = scale.factors();
+            if !(factors.x > 0.0 && factors.y > 0.0 && factors.z > 0.0) {
+                return invalid(path + ".scale", "must be positive");
+            }
+            keyframe = keyframe.with_scale(factors);
+        }
+        Ok(keyframe)
+    }
+}
+
+fn lookup_material(
+    materials: &HashMap<String, Arc<dyn Material>>,
+    name: &str,
+    path: &str,
+) -> Result<Arc<dyn Material>, SceneError> {
+    match materials.get(name) {
+        Some(material) => Ok(Arc::clone(material)),
+        None => invalid(
+            format!("{}.material", path),
+            &format!("unknown material `{}`", name),
+        ),
+    }
+}
+
+fn to_vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
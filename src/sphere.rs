@@ -1,18 +1,21 @@
-use crate::hittable::{HitResult, Hittable};
+use crate::aabb::{self, Aabb};
+use crate::hittable::{self, HitResult, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::{dot, Vec3};
 
-use std::rc::Rc;
+use std::f32::consts::PI;
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct Sphere {
     center: Vec3,
     radius: f32,
-    material: Rc<dyn Material>,
+    material: Arc<dyn Material>,
 }
 
 impl Sphere {
-    pub fn new(center: Vec3, radius: f32, material: Rc<dyn Material>) -> Self {
+    pub fn new(center: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
         Sphere {
             center,
             radius,
@@ -32,15 +35,11 @@ impl Hittable for Sphere {
             let root = discriminant.sqrt();
             let mut t = (-half_b - root) / a;
             if t > t_min && t < t_max {
-                let point = ray.at(t);
-                let normal = (point - self.center) / self.radius;
-                Some(HitResult::new(t, point, normal, Rc::clone(&self.material)))
+                Some(hit_result(ray, t, self.center, self.radius, &self.material))
             } else {
                 t = (-half_b + root) / a;
                 if t > t_min && t < t_max {
-                    let point = ray.at(t);
-                    let normal = (point - self.center) / self.radius;
-                    Some(HitResult::new(t, point, normal, Rc::clone(&self.material)))
+                    Some(hit_result(ray, t, self.center, self.radius, &self.material))
                 } else {
                     None
                 }
@@ -49,13 +48,70 @@ impl Hittable for Sphere {
             None
         }
     }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let r = Vec3::from(self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+
+    fn area(&self) -> f32 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        let normal = uniform_sphere(u);
+        Some((self.center + self.radius * normal, normal))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
+
+// Surface coordinates are spherical: u goes around the Y axis starting from -X, and v from the
+// bottom (-Y) to the top (+Y).
+fn hit_result(
+    ray: &Ray,
+    t: f32,
+    center: Vec3,
+    radius: f32,
+    material: &Arc<dyn Material>,
+) -> HitResult {
+    let point = ray.at(t);
+    let normal = (point - center) / radius;
+    let theta = (-normal.y).clamp(-1.0, 1.0).acos();
+    let phi = (-normal.z).atan2(normal.x) + PI;
+
+    let offset = point - center;
+    let dpdu = 2.0 * PI * Vec3::new(offset.z, 0.0, -offset.x);
+    // Along a meridian, pointing up; degenerate at the poles.
+    let ring_radius = (offset.x * offset.x + offset.z * offset.z).sqrt();
+    let dpdv = if ring_radius > 0.0 {
+        PI * Vec3::new(
+            -offset.x * offset.y / ring_radius,
+            ring_radius,
+            -offset.z * offset.y / ring_radius,
+        )
+    } else {
+        Vec3::default()
+    };
+
+    HitResult::with_uv(
+        t,
+        point,
+        normal,
+        (phi / (2.0 * PI), theta / PI),
+        Arc::clone(material),
+    )
+    .with_derivatives(dpdu, dpdv)
 }
 
+#[derive(Clone)]
 pub struct MovingSphere {
     center_range: (Vec3, Vec3),
     time_range: (f32, f32),
     radius: f32,
-    material: Rc<dyn Material>,
+    material: Arc<dyn Material>,
 }
 
 impl MovingSphere {
@@ -63,7 +119,7 @@ impl MovingSphere {
         center_range: (Vec3, Vec3),
         time_range: (f32, f32),
         radius: f32,
-        material: Rc<dyn Material>,
+        material: Arc<dyn Material>,
     ) -> Self {
         MovingSphere {
             center_range,
@@ -97,15 +153,23 @@ impl Hittable for MovingSphere {
             let root = discriminant.sqrt();
             let mut t = (-half_b - root) / a;
             if t > t_min && t < t_max {
-                let point = ray.at(t);
-                let normal = (point - self.center(ray.time)) / self.radius;
-                Some(HitResult::new(t, point, normal, Rc::clone(&self.material)))
+                Some(hit_result(
+                    ray,
+                    t,
+                    self.center(ray.time),
+                    self.radius,
+                    &self.material,
+                ))
             } else {
                 t = (-half_b + root) / a;
                 if t > t_min && t < t_max {
-                    let point = ray.at(t);
-                    let normal = (point - self.center(ray.time)) / self.radius;
-                    Some(HitResult::new(t, point, normal, Rc::clone(&self.material)))
+                    Some(hit_result(
+                        ray,
+                        t,
+                        self.center(ray.time),
+                        self.radius,
+                        &self.material,
+                    ))
                 } else {
                     None
                 }
@@ -114,4 +178,36 @@ impl Hittable for MovingSphere {
             None
         }
     }
+
+    // Motion is linear, so the boxes at both ends of the interval enclose the whole sweep.
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let r = Vec3::from(self.radius);
+        let c0 = self.center(time0);
+        let c1 = self.center(time1);
+        Some(aabb::surrounding_box(
+            &Aabb::new(c0 - r, c0 + r),
+            &Aabb::new(c1 - r, c1 + r),
+        ))
+    }
+
+    fn area(&self) -> f32 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self, u: (f32, f32), time: f32) -> Option<(Vec3, Vec3)> {
+        let normal = uniform_sphere(u);
+        Some((self.center(time) + self.radius * normal, normal))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
+
+// Unit vector spread uniformly over the sphere, for `u` in [0, 1)^2.
+fn uniform_sphere(u: (f32, f32)) -> Vec3 {
+    let y = 1.0 - 2.0 * u.0;
+    let ring_radius = (1.0 - y * y).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    Vec3::new(ring_radius * phi.cos(), y, ring_radius * phi.sin())
 }
@@ -0,0 +1,144 @@
+use crate::material::Material;
+use crate::mesh::{MeshBuffers, MeshError, MeshFace, TriangleMesh};
+use crate::vec3::Vec3;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const BINARY_HEADER_SIZE: usize = 84;
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+// Loads an ASCII or binary STL file with a single material. STL stores separate vertices for
+// every triangle, so identical positions are merged to build an indexed mesh; facet normals
+// are ignored in favour of the ones given by the vertex winding.
+pub fn load_stl(path: &Path, material: Arc<dyn Material>) -> Result<TriangleMesh, MeshError> {
+    let data = fs::read(path).map_err(|error| MeshError::Io {
+        file: path.to_path_buf(),
+        error,
+    })?;
+
+    // Binary files may also start with "solid", so trust the size first.
+    let triangles = if is_binary(&data) {
+        read_binary(&data)
+    } else if data.starts_with(b"solid") {
+        read_ascii(path, &data)?
+    } else {
+        return Err(MeshError::Invalid {
+            file: path.to_path_buf(),
+            message: "not an STL file, or truncated binary data".to_string(),
+        });
+    };
+
+    let mut buffers = MeshBuffers::default();
+    let mut indices: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut faces = Vec::with_capacity(triangles.len());
+    for triangle in triangles {
+        let mut positions = [0; 3];
+        for (k, vertex) in triangle.iter().enumerate() {
+            let key = [vertex.x.to_bits(), vertex.y.to_bits(), vertex.z.to_bits()];
+            positions[k] = *indices.entry(key).or_insert_with(|| {
+                buffers.positions.push(*vertex);
+                buffers.positions.len() - 1
+            });
+        }
+        faces.push(MeshFace {
+            positions,
+            normals: None,
+            uvs: None,
+            colors: None,
+            material: 0,
+        });
+    }
+
+    Ok(TriangleMesh::new(buffers, faces, vec![material]))
+}
+
+fn is_binary(data: &[u8]) -> bool {
+    if data.len() < BINARY_HEADER_SIZE {
+        return false;
+    }
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    data.len() == BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE
+}
+
+fn read_binary(data: &[u8]) -> Vec<[Vec3; 3]> {
+    let float = |offset: usize| {
+        f32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    data[BINARY_HEADER_SIZE..]
+        .chunks(BINARY_TRIANGLE_SIZE)
+        .enumerate()
+        .map(|(i, _)| {
+            // Skip the facet normal, and the attribute byte count at the end.
+            let offset = BINARY_HEADER_SIZE + i * BINARY_TRIANGLE_SIZE + 12;
+            let vertex = |k: usize| {
+                let offset = offset + 12 * k;
+                Vec3::new(float(offset), float(offset + 4), float(offset + 8))
+            };
+            [vertex(0), vertex(1), vertex(2)]
+        })
+        .collect()
+}
+
+fn read_ascii(path: &Path, data: &[u8]) -> Result<Vec<[Vec3; 3]>, MeshError> {
+    let text = String::from_utf8_lossy(data);
+    let mut triangles = Vec::new();
+    let mut vertices = Vec::with_capacity(3);
+    let mut in_loop = false;
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| MeshError::Parse {
+            file: path.to_path_buf(),
+            line: i + 1,
+            message,
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["outer", "loop"] => {
+                if in_loop {
+                    return Err(error("nested `outer loop`".to_string()));
+                }
+                in_loop = true;
+                vertices.clear();
+            }
+            ["vertex", x, y, z] => {
+                if !in_loop {
+                    return Err(error("`vertex` outside of `outer loop`".to_string()));
+                }
+                let mut coordinates = [0.0; 3];
+                for (coordinate, token) in coordinates.iter_mut().zip([x, y, z].iter()) {
+                    *coordinate = token
+                        .parse()
+                        .map_err(|_| error(format!("invalid number `{}`", token)))?;
+                }
+                vertices.push(Vec3::new(coordinates[0], coordinates[1], coordinates[2]));
+            }
+            ["vertex", ..] => return Err(error("`vertex` needs 3 coordinates".to_string())),
+            ["endloop"] => {
+                if vertices.len() != 3 {
+                    return Err(error(format!(
+                        "facet with {} vertices instead of 3",
+                        vertices.len()
+                    )));
+                }
+                in_loop = false;
+                triangles.push([vertices[0], vertices[1], vertices[2]]);
+            }
+            ["solid", ..] | ["endsolid", ..] | ["facet", ..] | ["endfacet"] | [] => {}
+            _ => return Err(error(format!("unexpected `{}`", line.trim()))),
+        }
+    }
+    if in_loop {
+        return Err(MeshError::Invalid {
+            file: path.to_path_buf(),
+            message: "unterminated `outer loop`".to_string(),
+        });
+    }
+    Ok(triangles)
+}
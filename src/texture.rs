@@ -0,0 +1,276 @@
+use crate::framebuffer::Framebuffer;
+use crate::hittable::HitResult;
+use crate::mipmap::{MipMap, TextureFilter, WrapMode};
+use crate::noise::{Perlin, Worley};
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::sync::Arc;
+
+// Color varying over a surface, looked up with the surface coordinates and the point of a hit.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3;
+
+    // Average value over the area of the surface seen through a pixel. Textures that can't
+    // alias don't need to filter.
+    fn filtered_value(&self, u: f32, v: f32, point: &Vec3, _footprint: &TextureFootprint) -> Vec3 {
+        self.value(u, v, point)
+    }
+}
+
+// Area seen through a pixel at a hit, as the changes in (u, v) along the two axes of its
+// ellipse on the surface. Zero when unknown, which turns filtering off.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TextureFootprint {
+    pub axes: [(f32, f32); 2],
+}
+
+impl TextureFootprint {
+    // Footprint of the cone around `ray` (see `Ray::spread`), stretched along the direction
+    // of the ray on the surface as it gets closer to grazing.
+    pub fn at(ray: &Ray, hit: &HitResult) -> Self {
+        let width = ray.spread * hit.t * ray.direction.length();
+        let (dpdu, dpdv) = (hit.dpdu, hit.dpdv);
+        let a = vec3::dot(&dpdu, &dpdu);
+        let b = vec3::dot(&dpdu, &dpdv);
+        let c = vec3::dot(&dpdv, &dpdv);
+        let determinant = a * c - b * b;
+        if width <= 0.0 || determinant <= 1e-12 * a * c || hit.normal.squared_length() == 0.0 {
+            return TextureFootprint::default();
+        }
+
+        let normal = Vec3::unit_from(hit.normal);
+        let direction = Vec3::unit_from(ray.direction);
+        let cosine = vec3::dot(&direction, &normal);
+        let along = direction - cosine * normal;
+        let major = if along.squared_length() > 1e-12 {
+            Vec3::unit_from(along)
+        } else {
+            Vec3::unit_from(dpdu)
+        };
+        let minor = vec3::cross(&normal, &major);
+
+        // Least squares solution of du * dpdu + dv * dpdv = axis.
+        let to_uv = |axis: Vec3| {
+            let pu = vec3::dot(&dpdu, &axis);
+            let pv = vec3::dot(&dpdv, &axis);
+            (
+                (c * pu - b * pv) / determinant,
+                (a * pv - b * pu) / determinant,
+            )
+        };
+        TextureFootprint {
+            axes: [
+                to_uv(width / cosine.abs().max(1e-3) * major),
+                to_uv(width * minor),
+            ],
+        }
+    }
+}
+
+pub struct ConstantTexture {
+    color: Vec3,
+}
+
+impl ConstantTexture {
+    pub fn new(color: Vec3) -> Self {
+        ConstantTexture { color }
+    }
+}
+
+impl Texture for ConstantTexture {
+    fn value(&self, _u: f32, _v: f32, _point: &Vec3) -> Vec3 {
+        self.color
+    }
+}
+
+// Solid checker pattern alternating two textures in space, with `scale` cells per 2π units.
+pub struct CheckerTexture {
+    odd: Arc<dyn Texture>,
+    even: Arc<dyn Texture>,
+    scale: f32,
+}
+
+impl CheckerTexture {
+    pub fn new(odd: Arc<dyn Texture>, even: Arc<dyn Texture>, scale: f32) -> Self {
+        CheckerTexture { odd, even, scale }
+    }
+}
+
+impl CheckerTexture {
+    fn pick(&self, point: &Vec3) -> &dyn Texture {
+        let sines = (self.scale * point.x).sin()
+            * (self.scale * point.y).sin()
+            * (self.scale * point.z).sin();
+        if sines < 0.0 {
+            self.odd.as_ref()
+        } else {
+            self.even.as_ref()
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        self.pick(point).value(u, v, point)
+    }
+
+    fn filtered_value(&self, u: f32, v: f32, point: &Vec3, footprint: &TextureFootprint) -> Vec3 {
+        self.pick(point).filtered_value(u, v, point, footprint)
+    }
+}
+
+// Image mapped over the [0, 1] range of the surface coordinates, with v pointing up.
+pub struct ImageTexture {
+    mipmap: MipMap,
+    filter: TextureFilter,
+}
+
+impl ImageTexture {
+    // Panics if the image is empty.
+    pub fn new(image: Framebuffer, wrap: WrapMode, filter: TextureFilter) -> Self {
+        ImageTexture {
+            mipmap: MipMap::new(image, wrap),
+            filter,
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        self.filtered_value(u, v, point, &TextureFootprint::default())
+    }
+
+    fn filtered_value(&self, u: f32, v: f32, _point: &Vec3, footprint: &TextureFootprint) -> Vec3 {
+        self.mipmap.lookup(self.filter, u, v, footprint.axes)
+    }
+}
+
+// Procedural textures below are defined in space and take the seed of their noise, so that
+// the same seed always gives the same pattern.
+
+// Marble: bands along Z warped by turbulence, going from `base` to `vein` color.
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f32,
+    base: Vec3,
+    vein: Vec3,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u64, scale: f32, base: Vec3, vein: Vec3) -> Self {
+        MarbleTexture {
+            noise: Perlin::new(seed),
+            scale,
+            base,
+            vein,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f32, _v: f32, point: &Vec3) -> Vec3 {
+        let point = self.scale * *point;
+        let t = 0.5 * (1.0 + (point.z + 10.0 * self.noise.turbulence(&point, 7)).sin());
+        lerp(self.vein, self.base, t)
+    }
+}
+
+// Wood: growth rings around the Y axis, distorted by fractal noise.
+pub struct WoodTexture {
+    noise: Perlin,
+    scale: f32,
+    light: Vec3,
+    dark: Vec3,
+}
+
+impl WoodTexture {
+    pub fn new(seed: u64, scale: f32, light: Vec3, dark: Vec3) -> Self {
+        WoodTexture {
+            noise: Perlin::new(seed),
+            scale,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f32, _v: f32, point: &Vec3) -> Vec3 {
+        let point = self.scale * *point;
+        let radius = (point.x * point.x + point.z * point.z).sqrt();
+        let rings = radius + 0.5 * self.noise.fbm(&(0.5 * point), 4, 2.0, 0.5);
+        // Rings fade in slowly and end sharply, as late wood does.
+        let t = rings - rings.floor();
+        lerp(self.light, self.dark, t * t * t)
+    }
+}
+
+// Clouds: fractal noise thresholded so that `coverage` (in [0, 1]) controls how much of the
+// sky they cover.
+pub struct CloudsTexture {
+    noise: Perlin,
+    scale: f32,
+    sky: Vec3,
+    cloud: Vec3,
+    coverage: f32,
+}
+
+impl CloudsTexture {
+    pub fn new(seed: u64, scale: f32, sky: Vec3, cloud: Vec3, coverage: f32) -> Self {
+        CloudsTexture {
+            noise: Perlin::new(seed),
+            scale,
+            sky,
+            cloud,
+            coverage: coverage.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Texture for CloudsTexture {
+    fn value(&self, _u: f32, _v: f32, point: &Vec3) -> Vec3 {
+        let density = 0.5 + 0.5 * self.noise.fbm(&(self.scale * *point), 6, 2.0, 0.5);
+        let threshold = 1.0 - self.coverage;
+        let t = if threshold < 1.0 {
+            ((density - threshold) / (1.0 - threshold)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        lerp(self.sky, self.cloud, t)
+    }
+}
+
+// Cells: Worley cells of `cell` color, shading into `border` color where two cells meet.
+pub struct CellsTexture {
+    noise: Worley,
+    scale: f32,
+    cell: Vec3,
+    border: Vec3,
+}
+
+impl CellsTexture {
+    pub fn new(seed: u64, scale: f32, cell: Vec3, border: Vec3) -> Self {
+        CellsTexture {
+            noise: Worley::new(seed),
+            scale,
+            cell,
+            border,
+        }
+    }
+}
+
+// Width of the border, as a difference between the distances to the two closest cells.
+const CELL_BORDER_WIDTH: f32 = 0.1;
+
+impl Texture for CellsTexture {
+    fn value(&self, _u: f32, _v: f32, point: &Vec3) -> Vec3 {
+        let (f1, f2) = self.noise.distances(&(self.scale * *point));
+        let t = ((f2 - f1) / CELL_BORDER_WIDTH).min(1.0);
+        lerp(self.border, self.cell, t)
+    }
+}
+
+fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    (1.0 - t) * a + t * b
+}
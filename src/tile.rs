@@ -0,0 +1,40 @@
+// Rectangular block of pixels, the unit of work handed to render threads.
+#[derive(Copy, Clone, Debug)]
+pub struct Tile {
+    pub index: usize,
+    pub x0: usize,
+    pub y0: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    // Seed for the tile's random stream: it depends only on the render seed and the tile
+    // position, so the output doesn't change with the number of threads or scheduling order.
+    pub fn seed(&self, render_seed: u64) -> u64 {
+        render_seed ^ (self.index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y0..self.y0 + self.height)
+            .flat_map(move |y| (self.x0..self.x0 + self.width).map(move |x| (x, y)))
+    }
+}
+
+// Splits a width x height image into tiles of at most size x size pixels, in row-major order.
+pub fn tiles(width: usize, height: usize, size: usize) -> Vec<Tile> {
+    debug_assert!(size > 0, "Tile size must be positive.");
+    let mut tiles = Vec::new();
+    for y0 in (0..height).step_by(size) {
+        for x0 in (0..width).step_by(size) {
+            tiles.push(Tile {
+                index: tiles.len(),
+                x0,
+                y0,
+                width: size.min(width - x0),
+                height: size.min(height - y0),
+            });
+        }
+    }
+    tiles
+}
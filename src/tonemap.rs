@@ -0,0 +1,96 @@
+use crate::vec3::Vec3;
+
+// Curves compressing unbounded linear radiance into the displayable [0, 1] range.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ToneMapOperator {
+    // Values above 1 are simply clipped.
+    Clamp,
+    // x / (1 + x)
+    Reinhard,
+    // Reinhard with a white point: radiance at `white` and above maps to 1.
+    ExtendedReinhard { white: f32 },
+    // Krzysztof Narkowicz's fit of the ACES filmic reference rendering transform.
+    Aces,
+    // John Hable's filmic curve from Uncharted 2.
+    Hable,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ToneMapping {
+    // Exposure adjustment in stops, applied before the operator.
+    pub exposure: f32,
+    pub operator: ToneMapOperator,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+        }
+    }
+}
+
+impl ToneMapping {
+    // Maps linear scene radiance to linear display values in [0, 1].
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let scale = 2f32.powf(self.exposure);
+        let map = |x: f32| {
+            let x = (scale * x).max(0.0);
+            let y = match self.operator {
+                ToneMapOperator::Clamp => x,
+                ToneMapOperator::Reinhard => x / (1.0 + x),
+                ToneMapOperator::ExtendedReinhard { white } => {
+                    x * (1.0 + x / (white * white)) / (1.0 + x)
+                }
+                ToneMapOperator::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+                ToneMapOperator::Hable => {
+                    const EXPOSURE_BIAS: f32 = 2.0;
+                    const WHITE: f32 = 11.2;
+                    hable_partial(EXPOSURE_BIAS * x) / hable_partial(WHITE)
+                }
+            };
+            y.clamp(0.0, 1.0)
+        };
+        Vec3::new(map(color.r()), map(color.g()), map(color.b()))
+    }
+
+    // Tone maps and encodes to 8-bit sRGB.
+    pub fn to_srgb8(&self, color: Vec3) -> [u8; 3] {
+        let color = self.apply(color);
+        let quantize = |x: f32| (255.0 * srgb_oetf(x) + 0.5) as u8;
+        [
+            quantize(color.r()),
+            quantize(color.g()),
+            quantize(color.b()),
+        ]
+    }
+}
+
+fn hable_partial(x: f32) -> f32 {
+    const A: f32 = 0.15; // Shoulder strength
+    const B: f32 = 0.50; // Linear strength
+    const C: f32 = 0.10; // Linear angle
+    const D: f32 = 0.20; // Toe strength
+    const E: f32 = 0.02; // Toe numerator
+    const F: f32 = 0.30; // Toe denominator
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+// sRGB opto-electronic transfer function (IEC 61966-2-1), for linear values in [0, 1].
+pub fn srgb_oetf(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Inverse of `srgb_oetf`, decoding sRGB values in [0, 1] to linear.
+pub fn srgb_eotf(encoded: f32) -> f32 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::aabb::Aabb;
+use crate::frame::{self, Frame};
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+// Ring torus around `axis`: a tube of `minor_radius` whose center line is the circle of
+// `major_radius` around `center`. u goes around the axis and v around the tube, starting from
+// the outer equator and going up first.
+#[derive(Clone)]
+pub struct Torus {
+    frame: Frame,
+    center: Vec3,
+    major_radius: f32,
+    minor_radius: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Torus {
+    // Panics if `axis` is zero or unless 0 < `minor_radius` < `major_radius`.
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(
+            minor_radius > 0.0 && minor_radius < major_radius,
+            "Torus tube must be thinner than the ring."
+        );
+        Torus {
+            frame: Frame::new(center, axis),
+            center,
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+
+    // Distances along a local ray, of unit direction, to where it crosses the surface.
+    fn intersections(&self, origin: Vec3, direction: Vec3) -> ([f64; 4], usize) {
+        let (major, minor) = (self.major_radius as f64, self.minor_radius as f64);
+
+        // The coefficients lose precision far away from the torus, so the quartic is solved from
+        // where the ray enters the bounding sphere.
+        let f = vec3::dot(&origin, &direction) as f64;
+        let c = origin.squared_length() as f64 - (major + minor).powi(2);
+        let discriminant = f * f - c;
+        if discriminant < 0.0 {
+            return ([0.0; 4], 0);
+        }
+        let shift = -f - discriminant.sqrt();
+        let o = [
+            origin.x as f64 + shift * direction.x as f64,
+            origin.y as f64 + shift * direction.y as f64,
+            origin.z as f64 + shift * direction.z as f64,
+        ];
+        let d = [direction.x as f64, direction.y as f64, direction.z as f64];
+
+        // (|p|^2 - R^2 - r^2)^2 = 4 R^2 (r^2 - z^2), with p = o + t d.
+        let f = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let e = o[0] * o[0] + o[1] * o[1] + o[2] * o[2] - major * major - minor * minor;
+        let four_r2 = 4.0 * major * major;
+        let (mut roots, count) = solve_quartic(
+            4.0 * f,
+            2.0 * e + 4.0 * f * f + four_r2 * d[2] * d[2],
+            4.0 * f * e + 2.0 * four_r2 * o[2] * d[2],
+            e * e - four_r2 * (minor * minor - o[2] * o[2]),
+        );
+        for root in roots.iter_mut().take(count) {
+            *root += shift;
+        }
+        (roots, count)
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let local = self.frame.ray_to_local(ray);
+        let length = local.direction.length();
+        let (distances, count) = self.intersections(local.origin, local.direction / length);
+        let t = distances[..count]
+            .iter()
+            .map(|&distance| distance as f32 / length)
+            .filter(|&t| t > t_min && t < t_max)
+            .fold(None, |closest: Option<f32>, t| {
+                Some(closest.map_or(t, |closest| closest.min(t)))
+            })?;
+
+        let point = local.at(t);
+        let ring = (point.x * point.x + point.y * point.y).sqrt();
+        let phi = frame::azimuth(point.x, point.y);
+        let theta = frame::azimuth(ring - self.major_radius, point.z);
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let normal = Vec3::new(cos_theta * cos_phi, cos_theta * sin_phi, sin_theta);
+        let hit = frame::LocalHit {
+            t,
+            normal,
+            uv: (phi / (2.0 * PI), theta / (2.0 * PI)),
+            dpdu: 2.0 * PI * Vec3::new(-point.y, point.x, 0.0),
+            dpdv: 2.0
+                * PI
+                * self.minor_radius
+                * Vec3::new(-sin_theta * cos_phi, -sin_theta * sin_phi, cos_theta),
+        };
+        Some(self.frame.hit_result(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let tube = Vec3::from(self.minor_radius);
+        let ring = frame::circle_bounds(self.center, self.frame.axis(), self.major_radius);
+        Some(Aabb::new(ring.min - tube, ring.max + tube))
+    }
+
+    fn area(&self) -> f32 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        // The outside of the tube is further from the axis, so it has more area: theta is drawn
+        // in proportion to R + r cos(theta) by inverting its cdf, (R theta + r sin(theta)) / 2 pi
+        // R, with Newton's method.
+        let (major, minor) = (self.major_radius, self.minor_radius);
+        let target = 2.0 * PI * major * u.0;
+        let mut theta = 2.0 * PI * u.0;
+        for _ in 0..8 {
+            let error = major * theta + minor * theta.sin() - target;
+            theta = (theta - error / (major + minor * theta.cos())).clamp(0.0, 2.0 * PI);
+        }
+        let phi = 2.0 * PI * u.1;
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let normal = Vec3::new(cos_theta * cos_phi, cos_theta * sin_phi, sin_theta);
+        let point = Vec3::new(major * cos_phi, major * sin_phi, 0.0) + minor * normal;
+        Some((
+            self.frame.point_to_world(point),
+            self.frame.vector_to_world(normal),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
+
+// Relative amount by which the discriminant of a double root, where a ray grazes the surface,
+// may come out below 0 through rounding.
+const DOUBLE_ROOT_TOLERANCE: f64 = 1e-8;
+
+// Real roots of x^4 + b x^3 + c x^2 + d x + e, by Ferrari's method, each refined by Newton's
+// method.
+fn solve_quartic(b: f64, c: f64, d: f64, e: f64) -> ([f64; 4], usize) {
+    // Depressed quartic y^4 + p y^2 + q y + r, with x = y - b / 4.
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut roots = [0.0; 4];
+    let mut count = 0;
+    let mut push_quadratic = |half_b: f64, c: f64| {
+        let discriminant = half_b * half_b - c;
+        if discriminant >= -DOUBLE_ROOT_TOLERANCE * (half_b * half_b + c.abs()) {
+            let root = discriminant.max(0.0).sqrt();
+            roots[count] = -half_b - root;
+            roots[count + 1] = -half_b + root;
+            count += 2;
+        }
+    };
+
+    // y^4 + p y^2 + q y + r = (y^2 + p / 2 + m)^2 - (2 m y^2 - q y + m^2 + p m + p^2 / 4 - r),
+    // where m is picked to make the second term a square: a positive root of the resolvent
+    // cubic.
+    let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+    if m > 1e-12 * (p.abs() + r.abs().sqrt()) {
+        let s = (2.0 * m).sqrt();
+        push_quadratic(-s / 2.0, p / 2.0 + m + q / (2.0 * s));
+        push_quadratic(s / 2.0, p / 2.0 + m - q / (2.0 * s));
+    } else {
+        // q is 0: a quadratic in y^2.
+        let discriminant = p * p / 4.0 - r;
+        if discriminant >= -DOUBLE_ROOT_TOLERANCE * (p * p / 4.0 + r.abs()) {
+            let root = discriminant.max(0.0).sqrt();
+            for &y2 in [-p / 2.0 - root, -p / 2.0 + root].iter() {
+                if y2 >= -DOUBLE_ROOT_TOLERANCE * (p.abs() / 2.0 + root) {
+                    push_quadratic(0.0, -y2.max(0.0));
+                }
+            }
+        }
+    }
+
+    let quartic = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    for x in roots.iter_mut().take(count) {
+        *x -= b / 4.0;
+        for _ in 0..2 {
+            // Near a double root the slope vanishes along with the value, and a step can throw
+            // the root away: steps are only taken when they get closer.
+            let slope = ((4.0 * *x + 3.0 * b) * *x + 2.0 * c) * *x + d;
+            let next = *x - quartic(*x) / slope;
+            if quartic(next).abs() < quartic(*x).abs() {
+                *x = next;
+            }
+        }
+    }
+    (roots, count)
+}
+
+// Largest real root of x^3 + a x^2 + b x + c.
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    // Depressed cubic t^3 + p t + q, with x = t - a / 3.
+    let a3 = a / 3.0;
+    let p = b - a * a3;
+    let q = 2.0 * a3 * a3 * a3 - a3 * b + c;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    let t = if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        (-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt()
+    } else if p < 0.0 {
+        // Three real roots, the largest of which is found trigonometrically.
+        let scale = (-p / 3.0).sqrt();
+        let cosine = (-q / (2.0 * scale * scale * scale)).clamp(-1.0, 1.0);
+        2.0 * scale * (cosine.acos() / 3.0).cos()
+    } else {
+        0.0
+    };
+    let mut x = t - a3;
+    for _ in 0..2 {
+        let slope = (3.0 * x + 2.0 * a) * x + b;
+        if slope != 0.0 {
+            x -= (((x + a) * x + b) * x + c) / slope;
+        }
+    }
+    x
+}
@@ -0,0 +1,349 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitResult, Hittable};
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::ops::Mul;
+use std::sync::Arc;
+
+type Matrix = [[f32; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// Transform of 3D space by a 4x4 matrix acting on column vectors (so the translation is in the
+// last column), kept along with its inverse. `a * b` applies `b` first, then `a`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix,
+    inverse: Matrix,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            matrix: IDENTITY,
+            inverse: IDENTITY,
+        }
+    }
+
+    // None if the matrix cannot be inverted.
+    pub fn from_matrix(matrix: Matrix) -> Option<Self> {
+        Some(Transform {
+            matrix,
+            inverse: invert(&matrix)?,
+        })
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for i in 0..3 {
+            matrix[i][3] = offset[i];
+            inverse[i][3] = -offset[i];
+        }
+        Transform { matrix, inverse }
+    }
+
+    // Negative factors mirror. Panics if a factor is 0.
+    pub fn scale(factors: Vec3) -> Self {
+        assert!(
+            factors.x != 0.0 && factors.y != 0.0 && factors.z != 0.0,
+            "Scale factors must not be zero."
+        );
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for i in 0..3 {
+            matrix[i][i] = factors[i];
+            inverse[i][i] = 1.0 / factors[i];
+        }
+        Transform { matrix, inverse }
+    }
+
+    // Rotation by `degrees` around `axis`, counterclockwise when looking down the axis towards
+    // the origin. Panics if `axis` is zero.
+    pub fn rotate(axis: Vec3, degrees: f32) -> Self {
+        assert!(
+            axis.squared_length() > 0.0,
+            "Rotation axis must not be zero."
+        );
+        let a = Vec3::unit_from(axis);
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        let matrix = [
+            [
+                t * a.x * a.x + cos,
+                t * a.x * a.y - sin * a.z,
+                t * a.x * a.z + sin * a.y,
+                0.0,
+            ],
+            [
+                t * a.x * a.y + sin * a.z,
+                t * a.y * a.y + cos,
+                t * a.y * a.z - sin * a.x,
+                0.0,
+            ],
+            [
+                t * a.x * a.z - sin * a.y,
+                t * a.y * a.z + sin * a.x,
+                t * a.z * a.z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        Transform {
+            matrix,
+            inverse: transpose(&matrix),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        self.matrix
+    }
+
+    pub fn inverse(&self) -> Self {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        let m = &self.matrix;
+        let transformed = self.vector(p) + Vec3::new(m[0][3], m[1][3], m[2][3]);
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 {
+            transformed
+        } else {
+            transformed / w
+        }
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.matrix;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    // Normals go through the inverse transpose to stay perpendicular to transformed surfaces.
+    // The result is not of unit length.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        let m = &self.inverse;
+        Vec3::new(
+            m[0][0] * n.x + m[1][0] * n.y + m[2][0] * n.z,
+            m[0][1] * n.x + m[1][1] * n.y + m[2][1] * n.z,
+            m[0][2] * n.x + m[1][2] * n.y + m[2][2] * n.z,
+        )
+    }
+
+    // The direction is not normalized, so that distances along the ray are the same before and
+    // after.
+    pub fn ray(&self, ray: &Ray) -> Ray {
+        Ray {
+            origin: self.point(ray.origin),
+            direction: self.vector(ray.direction),
+            ..*ray
+        }
+    }
+
+    // Box around the transformed box (Arvo, "Transforming Axis-Aligned Bounding Boxes").
+    pub fn bounding_box(&self, b: &Aabb) -> Aabb {
+        let m = &self.matrix;
+        let mut min = Vec3::new(m[0][3], m[1][3], m[2][3]);
+        let mut max = min;
+        for (i, row) in m.iter().take(3).enumerate() {
+            for (j, &value) in row.iter().take(3).enumerate() {
+                let (low, high) = (value * b.min[j], value * b.max[j]);
+                min[i] += low.min(high);
+                max[i] += low.max(high);
+            }
+        }
+        Aabb::new(min, max)
+    }
+
+    // Factor by which the transform scales every length, if it is made only of rotations,
+    // translations, mirrors and scales that are the same along every axis.
+    pub fn uniform_scale(&self) -> Option<f32> {
+        let m = &self.matrix;
+        if m[3] != [0.0, 0.0, 0.0, 1.0] {
+            return None;
+        }
+        let column = |j: usize| Vec3::new(m[0][j], m[1][j], m[2][j]);
+        let columns = [column(0), column(1), column(2)];
+        let squared = columns[0].squared_length();
+        let tolerance = 1e-4 * squared;
+        let orthogonal = (0..3).all(|i| {
+            (columns[i].squared_length() - squared).abs() <= tolerance
+                && vec3::dot(&columns[i], &columns[(i + 1) % 3]).abs() <= tolerance
+        });
+        if orthogonal {
+            Some(squared.sqrt())
+        } else {
+            None
+        }
+    }
+
+    // Determinant of the linear part, negative when the transform mirrors.
+    pub fn determinant(&self) -> f32 {
+        let m = &self.matrix;
+        let row = |i: usize| Vec3::new(m[i][0], m[i][1], m[i][2]);
+        vec3::dot(&row(0), &vec3::cross(&row(1), &row(2)))
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, other: Transform) -> Transform {
+        Transform {
+            matrix: multiply(&self.matrix, &other.matrix),
+            inverse: multiply(&other.inverse, &self.inverse),
+        }
+    }
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut product = [[0.0; 4]; 4];
+    for (i, row) in product.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    product
+}
+
+fn transpose(m: &Matrix) -> Matrix {
+    let mut transposed = [[0.0; 4]; 4];
+    for (i, row) in transposed.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = m[j][i];
+        }
+    }
+    transposed
+}
+
+// Gauss-Jordan elimination with partial pivoting, in double precision.
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let mut a = [[0f64; 8]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            a[i][j] = m[i][j] as f64;
+        }
+        a[i][4 + i] = 1.0;
+    }
+    for column in 0..4 {
+        let pivot = (column..4)
+            .max_by(|&r, &s| a[r][column].abs().total_cmp(&a[s][column].abs()))
+            .unwrap();
+        if a[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(column, pivot);
+        let scale = 1.0 / a[column][column];
+        for value in a[column].iter_mut() {
+            *value *= scale;
+        }
+        let pivot_row = a[column];
+        for (i, row) in a.iter_mut().enumerate() {
+            if i != column {
+                let factor = row[column];
+                for (value, pivot_value) in row.iter_mut().zip(pivot_row.iter()) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+    let mut inverse = [[0.0; 4]; 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            inverse[i][j] = a[i][4 + j] as f32;
+        }
+    }
+    Some(inverse)
+}
+
+// Object placed in the scene by a transform. Rays are taken into the space of the object
+// rather than the object into the scene, so instances of one object share its geometry: a mesh
+// can be placed any number of times for the memory of one.
+pub struct Transformed<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    transform: Transform,
+    uniform_scale: Option<f32>,
+}
+
+impl<H: Hittable + ?Sized> Transformed<H> {
+    pub fn new(object: Arc<H>, transform: Transform) -> Self {
+        Transformed {
+            object,
+            transform,
+            uniform_scale: transform.uniform_scale(),
+        }
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Transformed<H> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let mut hit = hit_transformed(self.object.as_ref(), &self.transform, ray, t_min, t_max)?;
+        hit.sampleable &= self.uniform_scale.is_some();
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let bounds = self.object.bounding_box(time0, time1)?;
+        Some(self.transform.bounding_box(&bounds))
+    }
+
+    // Only known when the transform scales area evenly, so other instances can't be sampled.
+    fn area(&self) -> f32 {
+        match self.uniform_scale {
+            Some(scale) => scale * scale * self.object.area(),
+            None => 0.0,
+        }
+    }
+
+    fn sample_surface(&self, u: (f32, f32), time: f32) -> Option<(Vec3, Vec3)> {
+        let (point, normal) = self.object.sample_surface(u, time)?;
+        Some((
+            self.transform.point(point),
+            Vec3::unit_from(self.transform.normal(normal)),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        self.object
+            .emitters()
+            .into_iter()
+            .map(|emitter| Arc::new(Transformed::new(emitter, self.transform)) as Arc<dyn Hittable>)
+            .collect()
+    }
+}
+
+// Intersection of a ray with `object` placed by `transform`, found in the space of the object.
+pub(crate) fn hit_transformed<H: Hittable + ?Sized>(
+    object: &H,
+    transform: &Transform,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<HitResult> {
+    let local = transform.inverse().ray(ray);
+    let mut hit = object.hit(&local, t_min, t_max)?;
+    hit.point = ray.at(hit.t);
+    hit.normal = Vec3::unit_from(transform.normal(hit.normal));
+    hit.dpdu = transform.vector(hit.dpdu);
+    hit.dpdv = transform.vector(hit.dpdv);
+    Some(hit)
+}
@@ -0,0 +1,242 @@
+use crate::aabb::{Aabb, FLAT_PADDING};
+use crate::hittable::{self, HitResult, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f32, f32); 3]>,
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    // The geometric normal faces the side from which the vertices appear counter-clockwise.
+    pub fn new(vertices: [Vec3; 3], material: Arc<dyn Material>) -> Self {
+        Triangle {
+            vertices,
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+
+    // Per-vertex normals, interpolated for smooth shading. Panics if one has zero length.
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        assert!(
+            normals.iter().all(|n| n.squared_length() > 0.0),
+            "Vertex normals must not be zero."
+        );
+        self.normals = Some(normals);
+        self
+    }
+
+    // Per-vertex texture coordinates; without them (u, v) are the barycentric coordinates
+    // of the hit point with respect to the second and third vertices.
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald, "Watertight Ray/Triangle
+// Intersection"), returning (t, b1, b2) where b1 and b2 are the barycentric coordinates of the
+// hit with respect to the second and third vertices.
+//
+// The vertices are moved into a space where the ray starts at the origin and runs along +z, so
+// the edge tests only depend on the x and y of each vertex and give the same result for both
+// triangles sharing an edge. Edge functions that come out as exactly 0 are recomputed in double
+// precision, and edges and vertices count as inside, so no ray slips between neighbours.
+pub(crate) fn intersect(
+    vertices: &[Vec3; 3],
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, f32, f32)> {
+    let d = ray.direction;
+    // Axis along which the ray goes furthest, which becomes z.
+    let kz = if d.x.abs() > d.y.abs() {
+        if d.x.abs() > d.z.abs() {
+            0
+        } else {
+            2
+        }
+    } else if d.y.abs() > d.z.abs() {
+        1
+    } else {
+        2
+    };
+    if d[kz] == 0.0 {
+        return None;
+    }
+    // Swapping x and y when the ray goes towards -z keeps the winding of the triangle.
+    let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+    if d[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    // Shear taking the direction to +z.
+    let shear_x = d[kx] / d[kz];
+    let shear_y = d[ky] / d[kz];
+    let shear_z = 1.0 / d[kz];
+
+    let relative = [
+        vertices[0] - ray.origin,
+        vertices[1] - ray.origin,
+        vertices[2] - ray.origin,
+    ];
+    let sheared = |v: Vec3| (v[kx] - shear_x * v[kz], v[ky] - shear_y * v[kz]);
+    let (ax, ay) = sheared(relative[0]);
+    let (bx, by) = sheared(relative[1]);
+    let (cx, cy) = sheared(relative[2]);
+
+    // Twice the signed areas of the triangles the ray makes with each edge, which weigh the
+    // opposite vertices.
+    let mut u = cx * by - cy * bx;
+    let mut v = ax * cy - ay * cx;
+    let mut w = bx * ay - by * ax;
+    if u == 0.0 || v == 0.0 || w == 0.0 {
+        let edge = |px: f32, py: f32, qx: f32, qy: f32| {
+            (px as f64 * qy as f64 - py as f64 * qx as f64) as f32
+        };
+        u = edge(cx, cy, bx, by);
+        v = edge(ax, ay, cx, cy);
+        w = edge(bx, by, ax, ay);
+    }
+    // The ray passes outside an edge, from either side of the triangle.
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+    let determinant = u + v + w;
+    // The ray is parallel to the triangle's plane.
+    if determinant == 0.0 {
+        return None;
+    }
+
+    let scaled_t = u * shear_z * relative[0][kz]
+        + v * shear_z * relative[1][kz]
+        + w * shear_z * relative[2][kz];
+    let t = scaled_t / determinant;
+    if t > t_min && t < t_max {
+        Some((t, v / determinant, w / determinant))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn bounding_box(vertices: &[Vec3; 3]) -> Aabb {
+    vertices
+        .iter()
+        .fold(Aabb::empty(), |acc, vertex| acc.grow(*vertex))
+        .padded(FLAT_PADDING)
+}
+
+// Linear combination of per-vertex attributes with barycentric weights.
+pub(crate) fn interpolate<T>(values: &[T; 3], b1: f32, b2: f32) -> T
+where
+    T: Copy + std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+{
+    values[0] * (1.0 - b1 - b2) + values[1] * b1 + values[2] * b2
+}
+
+pub(crate) fn geometric_normal(vertices: &[Vec3; 3]) -> Vec3 {
+    Vec3::unit_from(vec3::cross(
+        &(vertices[1] - vertices[0]),
+        &(vertices[2] - vertices[0]),
+    ))
+}
+
+pub(crate) fn area(vertices: &[Vec3; 3]) -> f32 {
+    0.5 * vec3::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).length()
+}
+
+// Point spread uniformly over the triangle, for `u` in [0, 1)^2.
+pub(crate) fn sample(vertices: &[Vec3; 3], u: (f32, f32)) -> Vec3 {
+    let root = u.0.sqrt();
+    interpolate(vertices, root * (1.0 - u.1), root * u.1)
+}
+
+// Texture coordinates giving the barycentric coordinates of the second and third vertices.
+pub(crate) const BARYCENTRIC_UVS: [(f32, f32); 3] = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+
+// Partial derivatives of the position with respect to the texture coordinates, or zero if the
+// coordinates are degenerate.
+pub(crate) fn uv_derivatives(vertices: &[Vec3; 3], uvs: &[(f32, f32); 3]) -> (Vec3, Vec3) {
+    let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+    let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+    let determinant = du1 * dv2 - dv1 * du2;
+    if determinant.abs() < 1e-12 {
+        return (Vec3::default(), Vec3::default());
+    }
+    let e1 = vertices[1] - vertices[0];
+    let e2 = vertices[2] - vertices[0];
+    (
+        (dv2 * e1 - dv1 * e2) / determinant,
+        (du1 * e2 - du2 * e1) / determinant,
+    )
+}
+
+// Interpolated vertex normal, kept in the same hemisphere as the geometric normal.
+pub(crate) fn shading_normal(
+    normals: &[Vec3; 3],
+    geometric_normal: Vec3,
+    b1: f32,
+    b2: f32,
+) -> Vec3 {
+    let interpolated = interpolate(normals, b1, b2);
+    // Opposite vertex normals can cancel out.
+    if interpolated.squared_length() == 0.0 {
+        return geometric_normal;
+    }
+    let normal = Vec3::unit_from(interpolated);
+    if vec3::dot(&normal, &geometric_normal) < 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitResult> {
+        let (t, b1, b2) = intersect(&self.vertices, ray, t_min, t_max)?;
+        let geometric_normal = geometric_normal(&self.vertices);
+        let normal = match &self.normals {
+            Some(normals) => shading_normal(normals, geometric_normal, b1, b2),
+            None => geometric_normal,
+        };
+        let uvs = self.uvs.unwrap_or(BARYCENTRIC_UVS);
+        let us = [uvs[0].0, uvs[1].0, uvs[2].0];
+        let vs = [uvs[0].1, uvs[1].1, uvs[2].1];
+        let (dpdu, dpdv) = uv_derivatives(&self.vertices, &uvs);
+        Some(
+            HitResult::with_uv(
+                t,
+                ray.at(t),
+                normal,
+                (interpolate(&us, b1, b2), interpolate(&vs, b1, b2)),
+                Arc::clone(&self.material),
+            )
+            .with_derivatives(dpdu, dpdv),
+        )
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(bounding_box(&self.vertices))
+    }
+
+    fn area(&self) -> f32 {
+        area(&self.vertices)
+    }
+
+    fn sample_surface(&self, u: (f32, f32), _time: f32) -> Option<(Vec3, Vec3)> {
+        Some((sample(&self.vertices, u), geometric_normal(&self.vertices)))
+    }
+
+    fn emitters(&self) -> Vec<Arc<dyn Hittable>> {
+        hittable::emitters_of(self, self.material.as_ref())
+    }
+}
@@ -1,4 +1,4 @@
-use rand::prelude::*;
+use crate::rng::random;
 
 use std::fmt;
 use std::ops::{
@@ -35,6 +35,17 @@ impl Vec3 {
         candidate
     }
 
+    // Uniformly distributed over the surface of the unit sphere.
+    pub fn random_unit_vector() -> Self {
+        loop {
+            let candidate = Vec3::random_in_unit_sphere();
+            let squared_length = candidate.squared_length();
+            if squared_length > 1e-12 {
+                return candidate / squared_length.sqrt();
+            }
+        }
+    }
+
     pub fn random() -> Self {
         Vec3::new(random::<f32>(), random::<f32>(), random::<f32>())
     }
@@ -1,22 +1,45 @@
+use crate::aabb::{self, Aabb};
+use crate::background::Background;
+use crate::bvh::BvhNode;
 use crate::hittable::{HitResult, Hittable};
+use crate::light::AreaLights;
 use crate::material::{Dielectric, Lambertian, Metallic};
 use crate::ray::Ray;
 use crate::sphere::{MovingSphere, Sphere};
-use crate::vec3::Vec3;
+use crate::vec3::{self, Vec3};
 
-use rand::prelude::*;
+use crate::rng::random;
 
 use std::f32;
-use std::rc::Rc;
+use std::sync::Arc;
+
+// Bounces after which paths may be ended by Russian roulette.
+const ROULETTE_DEPTH: i32 = 3;
 
 #[derive(Default)]
 pub struct World {
     hittables: Vec<Box<dyn Hittable>>,
+    background: Background,
+    // Emitting surfaces of the hittables, sampled directly at each diffuse bounce.
+    lights: AreaLights,
 }
 
 impl World {
     pub fn new(hittables: Vec<Box<dyn Hittable>>) -> Self {
-        World { hittables }
+        let mut world = World::default();
+        for hittable in hittables {
+            world.add(hittable);
+        }
+        world
+    }
+
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
     }
 
     pub fn random() -> Self {
@@ -26,7 +49,7 @@ impl World {
         hittables.push(Box::new(Sphere::new(
             Vec3::new(0.0, -1000.0, 0.0),
             1000.0,
-            Rc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+            Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
         )));
 
         for a in -11..11 {
@@ -43,7 +66,7 @@ impl World {
                             (center, center + Vec3::new(0.0, 0.5 * random::<f32>(), 0.0)),
                             (0.0, 1.0),
                             0.2,
-                            Rc::new(Lambertian::new(Vec3::new(
+                            Arc::new(Lambertian::new(Vec3::new(
                                 random::<f32>() * random::<f32>(),
                                 random::<f32>() * random::<f32>(),
                                 random::<f32>() * random::<f32>(),
@@ -53,7 +76,7 @@ impl World {
                         hittables.push(Box::new(Sphere::new(
                             center,
                             0.2,
-                            Rc::new(Metallic::new(
+                            Arc::new(Metallic::new(
                                 Vec3::new(
                                     0.5 * (1.0 + random::<f32>()),
                                     0.5 * (1.0 + random::<f32>()),
@@ -66,7 +89,7 @@ impl World {
                         hittables.push(Box::new(Sphere::new(
                             center,
                             0.2,
-                            Rc::new(Dielectric::new(1.5)),
+                            Arc::new(Dielectric::new(1.5)),
                         )));
                     }
                 }
@@ -76,47 +99,203 @@ impl World {
         hittables.push(Box::new(Sphere::new(
             Vec3::new(0.0, 1.0, 0.0),
             1.0,
-            Rc::new(Dielectric::new(1.5)),
+            Arc::new(Dielectric::new(1.5)),
         )));
         hittables.push(Box::new(Sphere::new(
             Vec3::new(-4.0, 1.0, 0.0),
             1.0,
-            Rc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1))),
+            Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1))),
         )));
         hittables.push(Box::new(Sphere::new(
             Vec3::new(4.0, 1.0, 0.0),
             1.0,
-            Rc::new(Metallic::new(Vec3::new(0.7, 0.6, 0.5), 0.0)),
+            Arc::new(Metallic::new(Vec3::new(0.7, 0.6, 0.5), 0.0)),
         )));
 
         World::new(hittables)
     }
 
     pub fn add(&mut self, hittable: Box<dyn Hittable>) {
+        let emitters = hittable.emitters();
+        if !emitters.is_empty() {
+            self.lights.extend(emitters);
+        }
         self.hittables.push(hittable);
     }
 
-    pub fn color(&self, ray: &Ray, depth: i32) -> Vec3 {
-        // Ignore hits very close to 0.
-        if let Some(hit) = self.hit(ray, 0.001, f32::MAX) {
-            let mut attenuation = Vec3::default();
-            let mut scattered = Ray::default();
-            // Limit to 50 rebounces.
-            if depth < 50
-                && hit
-                    .material
-                    .scatter(ray, &hit, &mut attenuation, &mut scattered)
-            {
-                attenuation * self.color(&scattered, depth + 1)
+    // Moves every bounded hittable into a single BVH, keeping unbounded ones in the flat list.
+    pub fn build_bvh(&mut self, time0: f32, time1: f32) {
+        let (bounded, mut unbounded): (Vec<_>, Vec<_>) = self
+            .hittables
+            .drain(..)
+            .partition(|hittable| hittable.bounding_box(time0, time1).is_some());
+        if !bounded.is_empty() {
+            unbounded.push(Box::new(BvhNode::new(bounded, time0, time1)));
+        }
+        self.hittables = unbounded;
+    }
+
+    // Radiance along the ray, following paths of at most `max_depth` bounces.
+    //
+    // At each bounce off a non-specular surface, a light (an emitting surface or the environment
+    // light) is sampled directly, and the light reached by the scattered ray is counted too:
+    // both estimates are combined with multiple importance sampling, so that each is weighted
+    // up where it is the better one.
+    //
+    // After a few bounces, paths are ended at random with a probability that grows as their
+    // throughput drops (Russian roulette), and the paths that go on are weighted up to make up
+    // for it, so that dim paths are cut short without biasing the result. `max_depth` then only
+    // bounds paths that keep all their energy, like those trapped inside glass.
+    pub fn color(&self, ray: &Ray, max_depth: i32) -> Vec3 {
+        let mut color = Vec3::default();
+        let mut throughput = Vec3::from(1.0);
+        let mut ray = *ray;
+        // Density with which `ray` was scattered by a non-specular surface, None for camera
+        // rays and specular bounces, where lights are not sampled.
+        let mut scattering_pdf: Option<f32> = None;
+
+        for bounce in 0..=max_depth {
+            // Ignore hits very close to 0.
+            let hit = match self.hit(&ray, 0.001, f32::MAX) {
+                Some(hit) => hit,
+                None => {
+                    let mut radiance = self.background.color(ray.direction);
+                    if let (Some(pdf), Some(light)) = (scattering_pdf, self.background.light()) {
+                        let light_pdf = self.environment_probability() * light.pdf(ray.direction);
+                        radiance *= power_heuristic(pdf, light_pdf);
+                    }
+                    color += throughput * radiance;
+                    break;
+                }
+            };
+
+            let mut emitted = hit.material.emitted(&ray, &hit);
+            if let Some(pdf) = scattering_pdf {
+                if hit.material.is_emissive() {
+                    emitted *= power_heuristic(pdf, self.area_light_pdf(&ray, &hit));
+                }
+            }
+            color += throughput * emitted;
+
+            if bounce == max_depth {
+                break;
+            }
+            let record = match hit.material.scatter(&ray, &hit) {
+                Some(record) => record,
+                None => break,
+            };
+            if record.is_specular {
+                scattering_pdf = None;
             } else {
-                Vec3::default()
+                color += throughput * self.sample_light(&ray, &hit);
+                scattering_pdf = Some(record.pdf);
             }
+            throughput *= record.throughput;
+
+            if bounce + 1 >= ROULETTE_DEPTH {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if random::<f32>() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+            ray = Ray::with_time(hit.point, record.direction, ray.time);
+        }
+        color
+    }
+
+    // Light reaching the hit point from one sampled light, and scattered back along `ray`.
+    fn sample_light(&self, ray: &Ray, hit: &HitResult) -> Vec3 {
+        let environment = self.background.light();
+        let sample_environment = match (self.lights.is_empty(), environment) {
+            (true, None) => return Vec3::default(),
+            (true, Some(_)) => true,
+            (false, None) => false,
+            (false, Some(_)) => random::<f32>() < 0.5,
+        };
+
+        let (direction, radiance, light_pdf) = if sample_environment {
+            let light = environment.unwrap();
+            let (direction, radiance, pdf) = light.sample((random::<f32>(), random::<f32>()));
+            if pdf == 0.0 || hit.material.eval(ray, hit, direction).squared_length() == 0.0 {
+                return Vec3::default();
+            }
+            let shadow_ray = Ray::with_time(hit.point, direction, ray.time);
+            if self.hit(&shadow_ray, 0.001, f32::MAX).is_some() {
+                return Vec3::default();
+            }
+            (direction, radiance, self.environment_probability() * pdf)
         } else {
-            let unit_direction = Vec3::unit_from(ray.direction);
-            let t = 0.5 * (unit_direction.y) + 1.0;
-            (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+            let u = (random::<f32>(), random::<f32>(), random::<f32>());
+            let (point, normal) = match self.lights.sample(u, ray.time) {
+                Some(sample) => sample,
+                None => return Vec3::default(),
+            };
+            let to_light = point - hit.point;
+            let distance = to_light.length();
+            if distance == 0.0 {
+                return Vec3::default();
+            }
+            let direction = to_light / distance;
+            let cosine = vec3::dot(&normal, &direction).abs();
+            if cosine == 0.0 || hit.material.eval(ray, hit, direction).squared_length() == 0.0 {
+                return Vec3::default();
+            }
+            // The light is visible if the first thing hit is the sampled point.
+            let shadow_ray = Ray::with_time(hit.point, direction, ray.time);
+            let radiance = match self.hit(&shadow_ray, 0.001, distance * (1.0 + 1e-3)) {
+                Some(light_hit) if light_hit.t > distance * (1.0 - 1e-3) => {
+                    light_hit.material.emitted(&shadow_ray, &light_hit)
+                }
+                _ => return Vec3::default(),
+            };
+            let pdf = self.area_light_probability() * self.lights.area_pdf() * distance * distance
+                / cosine;
+            (direction, radiance, pdf)
+        };
+
+        let scattering = hit.material.eval(ray, hit, direction);
+        let weight = power_heuristic(light_pdf, hit.material.pdf(ray, hit, direction));
+        weight / light_pdf * scattering * radiance
+    }
+
+    // Solid angle density of `sample_light` picking the point `hit` by `ray`, on an emitter.
+    // It is 0 for surfaces that light sampling can't pick.
+    pub fn area_light_pdf(&self, ray: &Ray, hit: &HitResult) -> f32 {
+        let cosine = vec3::dot(&Vec3::unit_from(ray.direction), &hit.normal).abs();
+        if !hit.sampleable || self.lights.is_empty() || cosine == 0.0 {
+            return 0.0;
+        }
+        let distance = hit.t * ray.direction.length();
+        self.area_light_probability() * self.lights.area_pdf() * distance * distance / cosine
+    }
+
+    // Chances of `sample_light` sampling emitting surfaces or the environment light.
+    fn area_light_probability(&self) -> f32 {
+        match (self.lights.is_empty(), self.background.light()) {
+            (true, _) => 0.0,
+            (false, None) => 1.0,
+            (false, Some(_)) => 0.5,
         }
     }
+
+    fn environment_probability(&self) -> f32 {
+        match self.background.light() {
+            Some(_) => 1.0 - self.area_light_probability(),
+            None => 0.0,
+        }
+    }
+}
+
+// Weight of a sample drawn with density `pdf` when another strategy could have drawn it with
+// density `other_pdf` (Veach's power heuristic, with an exponent of 2).
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        0.0
+    }
 }
 
 impl Hittable for World {
@@ -132,4 +311,16 @@ impl Hittable for World {
         }
         result
     }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let mut result: Option<Aabb> = None;
+        for hittable in self.hittables.iter() {
+            let bounding_box = hittable.bounding_box(time0, time1)?;
+            result = Some(match result {
+                Some(b) => aabb::surrounding_box(&b, &bounding_box),
+                None => bounding_box,
+            });
+        }
+        result
+    }
 }
@@ -0,0 +1,82 @@
+use raytracer::{Hittable, Lambertian, Material, Ray, Sphere, Triangle, Vec3, World};
+
+use std::sync::Arc;
+
+// Small deterministic generator, so that failures can be reproduced.
+fn sequence(seed: u64) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// Spheres and triangles of many sizes scattered through a 20 unit cube, overlapping often.
+fn objects(seed: u64) -> Vec<Box<dyn Hittable>> {
+    let mut random = sequence(seed);
+    let point = |random: &mut dyn FnMut() -> f32| {
+        Vec3::new(
+            20.0 * random() - 10.0,
+            20.0 * random() - 10.0,
+            20.0 * random() - 10.0,
+        )
+    };
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::from(0.5)));
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+    for _ in 0..300 {
+        let center = point(&mut random);
+        let radius = 0.05 + 1.5 * random() * random();
+        objects.push(Box::new(Sphere::new(center, radius, material.clone())));
+    }
+    for _ in 0..300 {
+        let corner = point(&mut random);
+        let size = 0.1 + 3.0 * random() * random();
+        let mut offset = || size * Vec3::new(random() - 0.5, random() - 0.5, random() - 0.5);
+        let vertices = [corner, corner + offset(), corner + offset()];
+        objects.push(Box::new(Triangle::new(vertices, material.clone())));
+    }
+    objects
+}
+
+#[test]
+fn bvh_matches_linear_scan() {
+    let linear = World::new(objects(3));
+    let mut bvh = World::new(objects(3));
+    bvh.build_bvh(0.0, 1.0);
+
+    let mut random = sequence(11);
+    let mut hits = 0;
+    for _ in 0..20000 {
+        let origin = Vec3::new(
+            30.0 * random() - 15.0,
+            30.0 * random() - 15.0,
+            30.0 * random() - 15.0,
+        );
+        // Aimed into the cube, where the objects are.
+        let target = Vec3::new(
+            20.0 * random() - 10.0,
+            20.0 * random() - 10.0,
+            20.0 * random() - 10.0,
+        );
+        let ray = Ray::new(origin, target - origin);
+        let expected = linear.hit(&ray, 0.001, f32::MAX);
+        let found = bvh.hit(&ray, 0.001, f32::MAX);
+        match (expected, found) {
+            (Some(expected), Some(found)) => {
+                assert_eq!(expected.t, found.t, "closest hit differs along {:?}", ray);
+                hits += 1;
+            }
+            (None, None) => {}
+            (expected, found) => panic!(
+                "linear scan hit at {:?}, BVH at {:?}, along {:?}",
+                expected.map(|hit| hit.t),
+                found.map(|hit| hit.t),
+                ray
+            ),
+        }
+    }
+    // Most rays should hit something, or the test proves little.
+    assert!(hits > 5000, "only {} hits", hits);
+}
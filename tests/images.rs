@@ -0,0 +1,74 @@
+use raytracer::{load_image, read_hdr, write_hdr, Framebuffer, Vec3};
+
+use std::io::Cursor;
+use std::path::Path;
+
+#[test]
+fn hdr_run_length_round_trip() {
+    // Scanlines mixing runs and literal dumps longer than the 127 and 128 byte limits of
+    // their counts, plus black pixels and values over 1.
+    let (width, height) = (300, 3);
+    let mut framebuffer = Framebuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let color = match x {
+                0..=139 => Vec3::new(0.25, 0.5, 0.75 + y as f32),
+                140..=149 => Vec3::default(),
+                _ => Vec3::new(
+                    (x * 7 % 256) as f32 / 64.0 + 0.01,
+                    (x * 13 % 256) as f32 / 32.0 + 0.01,
+                    y as f32 * 100.0 + x as f32,
+                ),
+            };
+            framebuffer.set(x, y, color);
+        }
+    }
+
+    let mut encoded = Vec::new();
+    write_hdr(&framebuffer, &mut encoded).unwrap();
+    // Runs make the file smaller than flat pixels would.
+    assert!(encoded.len() < 4 * width * height);
+    let decoded = read_hdr(&mut Cursor::new(&encoded)).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (width, height));
+    for y in 0..height {
+        for x in 0..width {
+            let (expected, actual) = (framebuffer.get(x, y), decoded.get(x, y));
+            // RGBE keeps 8 bits of mantissa for the largest component.
+            let tolerance = 1e-2 * expected.r().max(expected.g()).max(expected.b());
+            assert!(
+                (expected - actual).length() <= tolerance,
+                "({}, {}): {} != {}",
+                x,
+                y,
+                expected,
+                actual
+            );
+        }
+    }
+
+    // Decoding snaps to representable values, which then encode the same way again.
+    let mut reencoded = Vec::new();
+    write_hdr(&decoded, &mut reencoded).unwrap();
+    assert_eq!(encoded, reencoded);
+}
+
+#[test]
+fn hdr_header_too_large() {
+    for resolution in ["-Y 100000 +X 100000", "-Y 0 +X 18446744073709551615"].iter() {
+        let header = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", resolution);
+        let error = read_hdr(&mut Cursor::new(header.into_bytes())).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn hdr_run_count_too_long() {
+    // A pixel followed by nine empty old-style run markers, each of which would shift the
+    // count up by another byte.
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("run_overflow.hdr");
+    let error = load_image(&path).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,83 @@
+use raytracer::{
+    dot, DiffuseLight, Hittable, Material, Plane, Quad, Ray, Sphere, Transform, Transformed, Vec3,
+    World,
+};
+
+use std::sync::Arc;
+
+fn light() -> Arc<dyn Material> {
+    Arc::new(DiffuseLight::new(Vec3::from(4.0)))
+}
+
+// Small deterministic generator, so that failures can be reproduced.
+fn sequence(seed: u64) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// A 2x3 quad light facing down, a glowing floor plane and a glowing stretched sphere.
+fn world() -> (Quad, World) {
+    let quad = Quad::new(
+        Vec3::new(-1.0, 4.0, -1.5),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 3.0),
+        light(),
+    );
+    let sphere = Arc::new(Sphere::new(Vec3::default(), 1.0, light()));
+    let stretched = Transformed::new(
+        sphere,
+        Transform::translate(Vec3::new(6.0, 0.0, 0.0)) * Transform::scale(Vec3::new(1.0, 2.0, 1.0)),
+    );
+    let world = World::new(vec![
+        Box::new(quad.clone()),
+        Box::new(Plane::new(
+            Vec3::new(0.0, -2.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            light(),
+        )),
+        Box::new(stretched),
+    ]);
+    (quad, world)
+}
+
+#[test]
+fn quad_light_pdf_at_sampled_points() {
+    let (quad, world) = world();
+    let origin = Vec3::new(0.3, 0.5, 0.2);
+    let mut random = sequence(4);
+    for _ in 0..100 {
+        let (point, normal) = quad.sample_surface((random(), random()), 0.0).unwrap();
+        // Unnormalized directions, as the integrator's rays can have.
+        let ray = Ray::new(origin, 3.0 * (point - origin));
+        let hit = world.hit(&ray, 0.001, f32::MAX).unwrap();
+
+        let to_light = point - origin;
+        let distance = to_light.length();
+        let cosine = (dot(&to_light, &normal) / distance).abs();
+        let expected = distance * distance / (cosine * quad.area());
+        let pdf = world.area_light_pdf(&ray, &hit);
+        assert!(
+            (pdf - expected).abs() <= 1e-3 * expected,
+            "{} instead of {}",
+            pdf,
+            expected
+        );
+    }
+}
+
+#[test]
+fn unsampleable_lights_have_no_pdf() {
+    let (_, world) = world();
+    let origin = Vec3::new(0.0, 1.0, 0.0);
+    for target in [Vec3::new(1.0, -2.0, 0.5), Vec3::new(6.0, 1.5, 0.0)].iter() {
+        let ray = Ray::new(origin, *target - origin);
+        let hit = world.hit(&ray, 0.001, f32::MAX).unwrap();
+        assert!(!hit.sampleable);
+        assert_eq!(world.area_light_pdf(&ray, &hit), 0.0);
+    }
+}
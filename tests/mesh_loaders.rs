@@ -0,0 +1,244 @@
+use raytracer::{
+    load_gltf, load_mesh, HitResult, Hittable, Lambertian, Material, MeshError, Ray, Vec3,
+};
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn load(name: &str) -> Result<raytracer::TriangleMesh, MeshError> {
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::from(0.5)));
+    load_mesh(&fixture(name), material)
+}
+
+// Shoots a ray straight down the z axis at (x, y).
+fn hit_from_above(mesh: &dyn Hittable, x: f32, y: f32) -> Option<HitResult> {
+    let ray = Ray::new(Vec3::new(x, y, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    mesh.hit(&ray, 0.001, f32::MAX)
+}
+
+fn albedo(hit: &HitResult) -> Vec3 {
+    let ray = Ray::new(Vec3::default(), Vec3::new(0.0, 0.0, -1.0));
+    hit.material.scatter(&ray, hit).unwrap().throughput
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-3, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn ascii_ply_with_normals_and_colors() {
+    let mesh = load("quad_colors.ply").unwrap();
+    assert_eq!(mesh.triangle_count(), 2);
+
+    let bounds = mesh.bounding_box(0.0, 0.0).unwrap();
+    assert_close(bounds.min, Vec3::new(0.0, 0.0, 0.0));
+    assert_close(bounds.max, Vec3::new(1.0, 1.0, 0.0));
+
+    // Vertex colors blend across the faces, from red on the left to blue on the right.
+    let left = hit_from_above(&mesh, 0.1, 0.5).unwrap();
+    assert!((left.t - 5.0).abs() < 1e-4);
+    assert_close(left.normal, Vec3::new(0.0, 0.0, 1.0));
+    assert_close(albedo(&left), Vec3::new(0.9, 0.0, 0.1));
+    for &y in [0.2, 0.5, 0.8].iter() {
+        let middle = hit_from_above(&mesh, 0.5, y).unwrap();
+        assert_close(albedo(&middle), Vec3::new(0.5, 0.0, 0.5));
+    }
+    let right = hit_from_above(&mesh, 0.9, 0.5).unwrap();
+    assert_close(albedo(&right), Vec3::new(0.1, 0.0, 0.9));
+
+    assert!(hit_from_above(&mesh, 1.5, 0.5).is_none());
+}
+
+#[test]
+fn binary_ply_with_uvs_and_quad_faces() {
+    let mesh = load("quad_uvs.ply").unwrap();
+    assert_eq!(mesh.triangle_count(), 2);
+
+    let hit = hit_from_above(&mesh, 0.25, 0.75).unwrap();
+    assert!((hit.u - 0.25).abs() < 1e-4);
+    assert!((hit.v - 0.75).abs() < 1e-4);
+    // Without vertex colors, faces use the supplied material.
+    assert_close(albedo(&hit), Vec3::from(0.5));
+}
+
+#[test]
+fn ply_error_reports_line() {
+    match load("bad_index.ply") {
+        Err(MeshError::Parse { line, .. }) => assert_eq!(line, 13),
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("malformed file loaded"),
+    }
+}
+
+#[test]
+fn ply_list_count_beyond_data() {
+    // The count must not be trusted to size the list before reading it.
+    match load("huge_list.ply") {
+        Err(MeshError::Invalid { message, .. }) => {
+            assert!(message.contains("unexpected end"), "{}", message)
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("truncated file loaded"),
+    }
+}
+
+#[test]
+fn obj_polygons_relative_indices_and_materials() {
+    let mesh = load("pentagon.obj").unwrap();
+    // The pentagon is fanned into three triangles.
+    assert_eq!(mesh.triangle_count(), 5);
+
+    let red = Vec3::new(0.8, 0.1, 0.1);
+    for &(x, y) in [(0.9, 0.1), (0.9, 0.9), (0.5, 1.3)].iter() {
+        let hit = hit_from_above(&mesh, x, y).unwrap();
+        assert_close(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_close(albedo(&hit), red);
+    }
+    assert!(hit_from_above(&mesh, 0.2, 1.4).is_none());
+
+    // Negative indices count back from the last vertex read.
+    let blue = hit_from_above(&mesh, 2.2, 0.2).unwrap();
+    assert_close(albedo(&blue), Vec3::new(0.1, 0.1, 0.8));
+    // Faces before any `usemtl` get the default material.
+    let fallback = hit_from_above(&mesh, 4.2, 0.2).unwrap();
+    assert_close(albedo(&fallback), Vec3::from(0.5));
+    assert!(hit_from_above(&mesh, 2.9, 0.9).is_none());
+}
+
+#[test]
+fn obj_error_reports_line() {
+    match load("bad_face.obj") {
+        Err(MeshError::Parse { line, message, .. }) => {
+            assert_eq!(line, 4);
+            assert!(message.contains("out of range"), "{}", message);
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("malformed file loaded"),
+    }
+}
+
+#[test]
+fn obj_unknown_material_reports_line() {
+    match load("unknown_material.obj") {
+        Err(MeshError::Parse { line, message, .. }) => {
+            assert_eq!(line, 5);
+            assert!(message.contains("green"), "{}", message);
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("unknown material loaded"),
+    }
+}
+
+#[test]
+fn ascii_stl_merges_vertices() {
+    let mesh = load("tetrahedron.stl").unwrap();
+    assert_eq!(mesh.triangle_count(), 4);
+
+    let bounds = mesh.bounding_box(0.0, 0.0).unwrap();
+    assert_close(bounds.min, Vec3::new(0.0, 0.0, 0.0));
+    assert_close(bounds.max, Vec3::new(1.0, 1.0, 1.0));
+
+    // The slanted face is hit first, facing outwards.
+    let hit = hit_from_above(&mesh, 0.25, 0.25).unwrap();
+    assert!((hit.t - 4.5).abs() < 1e-4);
+    assert!(hit.normal.z > 0.0);
+}
+
+#[test]
+fn binary_stl_starting_with_solid() {
+    let mesh = load("quad_binary.stl").unwrap();
+    assert_eq!(mesh.triangle_count(), 2);
+    assert!(hit_from_above(&mesh, 0.75, 0.75).is_some());
+    assert!(hit_from_above(&mesh, -0.5, 0.5).is_none());
+}
+
+#[test]
+fn stl_error_reports_line() {
+    match load("bad_vertex.stl") {
+        Err(error @ MeshError::Parse { .. }) => {
+            assert!(error.to_string().contains("bad_vertex.stl:5:"), "{}", error)
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("malformed file loaded"),
+    }
+}
+
+#[test]
+fn gltf_nodes_materials_and_camera() {
+    for name in ["quads.gltf", "quads.glb"].iter() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::from(0.5)));
+        let scene = load_gltf(&fixture(name), material).unwrap();
+        assert_eq!(scene.mesh.triangle_count(), 4);
+
+        // Translated diffuse quad.
+        let hit = hit_from_above(&scene.mesh, 0.0, 0.0).unwrap();
+        assert!((hit.t - 7.0).abs() < 1e-4);
+        assert_close(albedo(&hit), Vec3::new(0.8, 0.1, 0.1));
+
+        // Mirrored metallic quad, still facing +z.
+        let hit = hit_from_above(&scene.mesh, 3.25, 0.0).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-4);
+        assert_close(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_close(albedo(&hit), Vec3::new(1.0, 0.8, 0.3));
+        assert!((hit.u - 0.25).abs() < 1e-4);
+
+        let camera = scene.camera(2.0).unwrap();
+        let ray = camera.ray_at(0.5, 0.5);
+        assert_close(ray.origin, Vec3::new(0.0, 0.0, 5.0));
+        assert_close(Vec3::unit_from(ray.direction), Vec3::new(0.0, 0.0, -1.0));
+    }
+}
+
+#[test]
+fn gltf_punctual_lights() {
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::from(0.5)));
+    let scene = load_gltf(&fixture("lights.gltf"), material).unwrap();
+    assert_eq!(scene.lights.len(), 3);
+    let emitted = |ray: &Ray| -> Option<(f32, Vec3)> {
+        let hit = scene
+            .lights
+            .iter()
+            .filter_map(|light| light.hit(ray, 0.001, f32::MAX))
+            .min_by(|a, b| a.t.total_cmp(&b.t))?;
+        Some((hit.t, hit.material.emitted(ray, &hit)))
+    };
+
+    // Point and spot lights are small spheres with the intensity of the light.
+    let down = Vec3::new(0.0, -1.0, 0.0);
+    let (t, radiance) = emitted(&Ray::new(Vec3::new(0.0, 5.0, 0.0), down)).unwrap();
+    let radius = 3.0 - t;
+    assert!(radius > 0.0 && radius < 0.1);
+    let area = std::f32::consts::PI * radius * radius;
+    // The radius is only known to the precision of the hit distance.
+    let assert_intensity = |radiance: Vec3, expected: Vec3| {
+        let intensity = area * radiance;
+        assert!(
+            (intensity - expected).length() < 1e-2 * expected.length(),
+            "{:?} != {:?}",
+            intensity,
+            expected
+        );
+    };
+    assert_intensity(radiance, Vec3::new(4.0, 2.0, 1.0));
+
+    // The spot light shines down only.
+    let (_, radiance) = emitted(&Ray::new(Vec3::new(2.0, -5.0, 0.0), -down)).unwrap();
+    assert_intensity(radiance, Vec3::from(2.0));
+    let (_, radiance) = emitted(&Ray::new(Vec3::new(2.0, 5.0, 0.0), down)).unwrap();
+    assert_close(radiance, Vec3::default());
+
+    // The directional light is a distant disk overhead, with a radius a tenth of its
+    // distance.
+    let (t, radiance) = emitted(&Ray::new(Vec3::new(0.5, 0.0, 0.5), -down)).unwrap();
+    assert!(t > 10.0);
+    let solid_angle = 2.0 * std::f32::consts::PI * (1.0 - 1.0 / 1.01f32.sqrt());
+    assert_close(solid_angle * radiance, Vec3::from(3.0));
+    assert!(emitted(&Ray::new(Vec3::new(0.5, 0.0, 0.5), down)).is_none());
+}
@@ -0,0 +1,72 @@
+use raytracer::{
+    Camera, Dielectric, DiffuseLight, Framebuffer, Hittable, Lambertian, RenderSettings, Renderer,
+    Sphere, Vec3, World,
+};
+
+use std::sync::Arc;
+
+fn renderer() -> Renderer {
+    let settings = RenderSettings {
+        width: 24,
+        height: 16,
+        samples_per_pixel: 4,
+        max_depth: 8,
+        seed: 7,
+        tile_size: 4,
+        ..RenderSettings::default()
+    };
+    let camera = Camera::new(
+        Vec3::new(0.0, 1.0, 4.0),
+        Vec3::new(0.0, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        settings.aspect_ratio(),
+        0.1,
+        4.0,
+        (0.0, 1.0),
+    );
+    let hittables: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere::new(
+            Vec3::new(0.0, -100.0, 0.0),
+            100.0,
+            Arc::new(Lambertian::new(Vec3::from(0.5))),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(-0.6, 0.5, 0.0),
+            0.5,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(0.6, 0.5, 0.0),
+            0.5,
+            Arc::new(DiffuseLight::new(Vec3::from(4.0))),
+        )),
+    ];
+    Renderer::new(settings, camera, World::new(hittables))
+}
+
+fn render_with_threads(threads: usize) -> Framebuffer {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    pool.install(|| renderer().render())
+}
+
+#[test]
+fn render_is_the_same_on_any_number_of_threads() {
+    let one = render_with_threads(1);
+    let four = render_with_threads(4);
+    for (a, b) in one.pixels().iter().zip(four.pixels().iter()) {
+        assert!(
+            a.x.to_bits() == b.x.to_bits()
+                && a.y.to_bits() == b.y.to_bits()
+                && a.z.to_bits() == b.z.to_bits(),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+    // The scene is lit, so the comparison above means something.
+    assert!(one.pixels().iter().any(|p| p.length() > 0.0));
+}
@@ -0,0 +1,135 @@
+use raytracer::{Distribution1D, Distribution2D, EnvironmentLight, Framebuffer, Vec3};
+
+use std::f32::consts::PI;
+
+// Small deterministic generator, so that failures can be reproduced.
+fn sequence(seed: u64) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance * expected.abs().max(1.0),
+        "{} is not close to {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn distribution_1d_pdf() {
+    let distribution = Distribution1D::new(&[1.0, 0.0, 3.0, 2.0, 0.5]);
+    let steps = 1000;
+    let integral: f32 = (0..steps)
+        .map(|i| distribution.pdf((i as f32 + 0.5) / steps as f32))
+        .sum::<f32>()
+        / steps as f32;
+    assert_close(integral, 1.0, 1e-4);
+
+    let mut random = sequence(1);
+    for _ in 0..10000 {
+        let (x, pdf, index) = distribution.sample(random());
+        assert!((0.0..1.0).contains(&x), "{}", x);
+        assert_ne!(index, 1, "sampled an empty bucket");
+        assert_eq!(pdf, distribution.pdf(x), "at {}", x);
+    }
+}
+
+#[test]
+fn distribution_2d_pdf() {
+    let function: Vec<f32> = (0..24).map(|i| ((i * 7) % 5) as f32).collect();
+    let distribution = Distribution2D::new(&function, 6);
+    let steps = 240;
+    let mut integral = 0.0;
+    for y in 0..steps {
+        for x in 0..steps {
+            let point = (
+                (x as f32 + 0.5) / steps as f32,
+                (y as f32 + 0.5) / steps as f32,
+            );
+            integral += distribution.pdf(point);
+        }
+    }
+    assert_close(integral / (steps * steps) as f32, 1.0, 1e-4);
+
+    let mut random = sequence(2);
+    for _ in 0..10000 {
+        let (point, pdf) = distribution.sample((random(), random()));
+        assert!(pdf > 0.0);
+        assert_eq!(pdf, distribution.pdf(point), "at {:?}", point);
+    }
+}
+
+// A dim gradient with a bright spot, so that the density is far from uniform.
+fn environment() -> EnvironmentLight {
+    let mut image = Framebuffer::new(16, 8);
+    for y in 0..8 {
+        for x in 0..16 {
+            image.set(x, y, Vec3::from(0.1 + 0.05 * x as f32 + 0.2 * y as f32));
+        }
+    }
+    image.set(11, 2, Vec3::from(50.0));
+    EnvironmentLight::new(image, 30.0, 1.0)
+}
+
+// Bins of equal solid angle: bands of equal height in y, split into equal angles around it.
+const BANDS: usize = 8;
+const SECTORS: usize = 8;
+
+fn bin(direction: Vec3) -> usize {
+    let band = ((1.0 - direction.y) / 2.0 * BANDS as f32) as usize;
+    let angle = direction.z.atan2(direction.x) + PI;
+    let sector = (angle / (2.0 * PI) * SECTORS as f32) as usize;
+    band.min(BANDS - 1) * SECTORS + sector.min(SECTORS - 1)
+}
+
+#[test]
+fn environment_pdf_matches_samples() {
+    let light = environment();
+
+    // Integrate the density over the sphere, in spherical coordinates.
+    let (thetas, phis) = (400, 800);
+    let cell = (PI / thetas as f32) * (2.0 * PI / phis as f32);
+    let mut expected = vec![0.0; BANDS * SECTORS];
+    for i in 0..thetas {
+        let theta = (i as f32 + 0.5) / thetas as f32 * PI;
+        for j in 0..phis {
+            let phi = (j as f32 + 0.5) / phis as f32 * 2.0 * PI;
+            let direction = Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            );
+            expected[bin(direction)] += light.pdf(direction) * theta.sin() * cell;
+        }
+    }
+    assert_close(expected.iter().sum(), 1.0, 1e-3);
+
+    // The samples land in each bin as often as the density says, and come with the same
+    // density `pdf` gives for their direction.
+    let count = 200_000;
+    let mut observed = vec![0usize; BANDS * SECTORS];
+    let mut random = sequence(3);
+    for _ in 0..count {
+        let (direction, _, pdf) = light.sample((random(), random()));
+        assert_close(direction.length(), 1.0, 1e-4);
+        assert_close(pdf, light.pdf(direction), 1e-3);
+        observed[bin(direction)] += 1;
+    }
+    for (i, (&observed, &expected)) in observed.iter().zip(expected.iter()).enumerate() {
+        let expected = expected * count as f32;
+        assert!(
+            (observed as f32 - expected).abs() <= 5.0 * expected.sqrt() + 10.0,
+            "bin {}: {} samples, expected {}",
+            i,
+            observed,
+            expected
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use raytracer::{Scene, SceneError};
+
+use std::path::Path;
+
+fn load(source: &str) -> Result<Scene, SceneError> {
+    Scene::from_json(source, Path::new("."), 1.5)
+}
+
+fn error(source: &str) -> SceneError {
+    match load(source) {
+        Ok(_) => panic!("invalid scene loaded"),
+        Err(error) => error,
+    }
+}
+
+// A scene with a single object, which may refer to the material `grey`.
+fn with_object(object: &str) -> String {
+    format!(
+        r#"{{
+  "camera": {{ "look_from": [0, 1, 5], "look_at": [0, 0, 0], "vertical_fov": 40, "focus_distance": 5 }},
+  "materials": {{ "grey": {{ "lambertian": {{ "albedo": [0.5, 0.5, 0.5] }} }} }},
+  "objects": [
+    {{ "sphere": {{ "center": [0, 0, 0], "radius": 1, "material": "grey" }} }},
+    {}
+  ]
+}}"#,
+        object
+    )
+}
+
+#[test]
+fn valid_scene_loads() {
+    let source =
+        with_object(r#"{ "sphere": { "center": [2, 0, 0], "radius": 0.5, "material": "grey" } }"#);
+    assert!(load(&source).is_ok());
+}
+
+#[test]
+fn shape_error_reports_position_and_field() {
+    // The radius is a string, on line 6.
+    let source = with_object(
+        r#"{ "sphere": { "center": [2, 0, 0], "radius": "big", "material": "grey" } }"#,
+    );
+    let error = error(&source);
+    match &error {
+        SceneError::Parse { path, line, .. } => {
+            assert_eq!(path, "objects[1].sphere.radius");
+            assert_eq!(*line, 6);
+        }
+        _ => panic!("unexpected error: {}", error),
+    }
+    let message = error.to_string();
+    assert!(
+        message.starts_with("line 6, column ") && message.contains(": objects[1].sphere.radius: "),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn unknown_material_is_invalid() {
+    let source =
+        with_object(r#"{ "sphere": { "center": [2, 0, 0], "radius": 0.5, "material": "gold" } }"#);
+    match error(&source) {
+        SceneError::Invalid { path, message } => {
+            assert_eq!(path, "objects[1].sphere.material");
+            assert!(message.contains("unknown material `gold`"), "{}", message);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn non_positive_radius_is_invalid() {
+    for radius in ["0", "-1"].iter() {
+        let source = with_object(&format!(
+            r#"{{ "sphere": {{ "center": [2, 0, 0], "radius": {}, "material": "grey" }} }}"#,
+            radius
+        ));
+        match error(&source) {
+            SceneError::Invalid { path, message } => {
+                assert_eq!(path, "objects[1].sphere.radius");
+                assert_eq!(message, "must be positive");
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use raytracer::{
+    Hittable, Lambertian, Material, Plane, Ray, Sphere, Torus, Transform, Transformed, Triangle,
+    Vec3,
+};
+
+use std::sync::Arc;
+
+fn material() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Vec3::from(0.5)))
+}
+
+// Small deterministic generator, so that failures can be reproduced.
+fn sequence(seed: u64) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[test]
+fn triangle_fan_is_watertight() {
+    // A fan around an off-center vertex, with coordinates that aren't exactly representable.
+    let center = Vec3::new(0.1, 0.2, 0.3);
+    let rim: Vec<Vec3> = (0..7)
+        .map(|i| {
+            let angle = i as f32 * 2.0 * std::f32::consts::PI / 7.0;
+            Vec3::new(
+                0.1 + angle.cos(),
+                0.2 + angle.sin(),
+                0.3 + 0.1 * angle.sin(),
+            )
+        })
+        .collect();
+    let triangles: Vec<Triangle> = (0..7)
+        .map(|i| Triangle::new([center, rim[i], rim[(i + 1) % 7]], material()))
+        .collect();
+
+    // Rays from all around aimed at points on the shared edges, which must hit one of the
+    // triangles on either side.
+    let mut random = sequence(7);
+    for n in 0..20000 {
+        let edge = rim[n % 7];
+        let target = center + random() * (edge - center);
+        let origin = Vec3::new(
+            4.0 * random() - 2.0,
+            4.0 * random() - 2.0,
+            if n % 2 == 0 { 3.0 } else { -3.0 } * (0.01 + random()),
+        );
+        let ray = Ray::new(origin, target - origin);
+        assert!(
+            triangles
+                .iter()
+                .any(|t| t.hit(&ray, 0.0, f32::MAX).is_some()),
+            "ray from {:?} slipped through the edge at {:?}",
+            origin,
+            target
+        );
+    }
+}
+
+#[test]
+fn triangle_barycentrics() {
+    let triangle = Triangle::new(
+        [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+        ],
+        material(),
+    );
+    let ray = Ray::new(Vec3::new(0.5, 1.0, 4.0), Vec3::new(0.0, 0.0, -2.0));
+    let hit = triangle.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!((hit.t - 2.0).abs() < 1e-6);
+    assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6);
+    assert!((hit.normal.z - 1.0).abs() < 1e-6);
+
+    let beside = Ray::new(Vec3::new(1.5, 1.5, 4.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(triangle.hit(&beside, 0.001, f32::MAX).is_none());
+}
+
+#[test]
+fn light_sampling_needs_a_known_area() {
+    // Surfaces light sampling can't pick are marked on their hits, so that the integrator gives
+    // their emission full weight.
+    let down = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let sampleable = |object: &dyn Hittable| object.hit(&down, 0.001, f32::MAX).unwrap().sampleable;
+
+    let sphere = Arc::new(Sphere::new(Vec3::default(), 1.0, material()));
+    assert!(sampleable(sphere.as_ref()));
+    assert!(!sampleable(&Plane::new(
+        Vec3::default(),
+        Vec3::new(0.0, 1.0, 0.0),
+        material()
+    )));
+
+    let uniform = Transformed::new(sphere.clone(), Transform::scale(Vec3::from(2.0)));
+    assert!(uniform.area() > 0.0 && sampleable(&uniform));
+    let stretched = Transformed::new(sphere, Transform::scale(Vec3::new(1.0, 2.0, 1.0)));
+    assert!(stretched.area() == 0.0 && !sampleable(&stretched));
+}
+
+// Ring of radius 1 around the z axis, with a tube of radius 0.25.
+fn torus() -> Torus {
+    Torus::new(
+        Vec3::default(),
+        Vec3::new(0.0, 0.0, 1.0),
+        1.0,
+        0.25,
+        material(),
+    )
+}
+
+// Distances to every crossing of the surface along `ray`, nearest first.
+fn crossings(object: &dyn Hittable, ray: &Ray) -> Vec<f32> {
+    let mut distances = Vec::new();
+    let mut t_min = 0.0;
+    while let Some(hit) = object.hit(ray, t_min, f32::MAX) {
+        distances.push(hit.t);
+        t_min = hit.t + 1e-3;
+    }
+    distances
+}
+
+#[test]
+fn torus_ray_through_the_hole() {
+    let down = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(torus().hit(&down, 0.0, f32::MAX).is_none());
+    let slanted = Ray::new(Vec3::new(0.5, 0.3, 5.0), Vec3::new(-0.1, -0.06, -1.0));
+    assert!(torus().hit(&slanted, 0.0, f32::MAX).is_none());
+}
+
+#[test]
+fn torus_ray_across_both_sides() {
+    let across = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let distances = crossings(&torus(), &across);
+    assert_eq!(distances.len(), 4, "{:?}", distances);
+    for (t, expected) in distances.iter().zip([3.75, 4.25, 5.75, 6.25].iter()) {
+        assert!((t - expected).abs() < 1e-4, "{} != {}", t, expected);
+    }
+    let hit = torus().hit(&across, 0.0, f32::MAX).unwrap();
+    assert!((hit.normal.x + 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn torus_ray_tangent_to_the_tube() {
+    // Grazing the top of the tube touches it at a double root on either side of the hole.
+    let grazing = Ray::new(Vec3::new(-5.0, 0.0, 0.25), Vec3::new(1.0, 0.0, 0.0));
+    let hit = torus().hit(&grazing, 0.0, f32::MAX).unwrap();
+    assert!((hit.t - 4.0).abs() < 1e-2, "{}", hit.t);
+    assert!((hit.normal.z - 1.0).abs() < 1e-3);
+
+    let above = Ray::new(Vec3::new(-5.0, 0.0, 0.251), Vec3::new(1.0, 0.0, 0.0));
+    assert!(torus().hit(&above, 0.0, f32::MAX).is_none());
+    // Tangent to the outer equator, from above.
+    let outside = Ray::new(Vec3::new(1.25, -3.0, 4.0), Vec3::new(0.0, 3.0, -4.0));
+    let hit = torus().hit(&outside, 0.0, f32::MAX).unwrap();
+    assert!((hit.point - Vec3::new(1.25, 0.0, 0.0)).length() < 1e-2);
+}
+
+#[test]
+fn torus_hit_from_far_away() {
+    let direction = Vec3::new(1.0, 0.2, 0.05);
+    let target = Vec3::new(-1.2, 0.1, 0.1);
+    let far = Ray::new(target - 1e4 * direction, direction);
+    let hit = torus().hit(&far, 0.0, f32::MAX).unwrap();
+
+    // The hit is on the surface, where the ray first crosses it.
+    let p = hit.point;
+    let ring = (p.x * p.x + p.y * p.y).sqrt();
+    let tube = ((ring - 1.0).powi(2) + p.z * p.z).sqrt();
+    assert!((tube - 0.25).abs() < 1e-2, "{:?} is off the surface", p);
+    assert!(p.x < -1.0 && p.x > -1.3, "{:?}", p);
+}
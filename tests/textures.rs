@@ -0,0 +1,65 @@
+use raytracer::{CellsTexture, Texture, Vec3, Worley};
+
+// Small deterministic generator, so that failures can be reproduced.
+fn sequence(seed: u64) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[test]
+fn worley_distances_are_continuous() {
+    // F1 and F2 are distances to sets of points, so they can't change faster than the point
+    // moves. A feature point missed by the search shows up as a jump, usually at a cell face.
+    let worley = Worley::new(5);
+    let mut random = sequence(11);
+    for _ in 0..20000 {
+        let a = Vec3::new(random(), random(), random()) * 8.0 - Vec3::from(4.0);
+        let step = (Vec3::new(random(), random(), random()) - Vec3::from(0.5)) * 0.05;
+        let b = a + step;
+        let (a1, a2) = worley.distances(&a);
+        let (b1, b2) = worley.distances(&b);
+        assert!(a1 <= a2, "F1 {} > F2 {} at {:?}", a1, a2, a);
+        let bound = step.length() + 1e-4;
+        assert!(
+            (a1 - b1).abs() <= bound,
+            "F1 jumps between {:?} and {:?}",
+            a,
+            b
+        );
+        assert!(
+            (a2 - b2).abs() <= bound,
+            "F2 jumps between {:?} and {:?}",
+            a,
+            b
+        );
+    }
+}
+
+#[test]
+fn cells_shade_into_their_borders() {
+    let cell = Vec3::new(0.9, 0.6, 0.3);
+    let border = Vec3::new(0.1, 0.1, 0.1);
+    let texture = CellsTexture::new(5, 2.0, cell, border);
+    let worley = Worley::new(5);
+    let mut random = sequence(3);
+    let (mut inside, mut on_border) = (0, 0);
+    for _ in 0..2000 {
+        let point = Vec3::new(random(), random(), random()) * 4.0;
+        let (f1, f2) = worley.distances(&(2.0 * point));
+        let value = texture.value(0.0, 0.0, &point);
+        if f2 - f1 >= 0.1 {
+            assert_eq!((value.x, value.y, value.z), (cell.x, cell.y, cell.z));
+            inside += 1;
+        } else {
+            // Between the two colors, darker the closer the point is to the border.
+            assert!(value.x >= border.x && value.x < cell.x, "{:?}", value);
+            on_border += 1;
+        }
+    }
+    assert!(inside > 0 && on_border > 0);
+}
@@ -0,0 +1,143 @@
+use raytracer::{
+    Animated, AnimatedTransform, Hittable, Keyframe, Lambertian, Material, Quad, Quaternion, Ray,
+    Sphere, Transform, Transformed, Vec3,
+};
+
+use std::sync::Arc;
+
+fn material() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Vec3::from(0.5)))
+}
+
+// Unit square in the z = 0 plane, facing +Z, with (u, v) = (x, y).
+fn unit_quad() -> Arc<Quad> {
+    Arc::new(Quad::new(
+        Vec3::default(),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        material(),
+    ))
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-3, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn instances_share_an_object() {
+    let quad = unit_quad();
+    // Doubled, stood up to face +X, then moved along X.
+    let transform = Transform::translate(Vec3::new(3.0, 0.0, 0.0))
+        * Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0)
+        * Transform::scale(Vec3::from(2.0));
+    let instance = Transformed::new(Arc::clone(&quad), transform);
+
+    let bounds = instance.bounding_box(0.0, 0.0).unwrap();
+    assert_close(bounds.min, Vec3::new(3.0, 0.0, -2.0));
+    assert_close(bounds.max, Vec3::new(3.0, 2.0, 0.0));
+    assert!((instance.area() - 4.0).abs() < 1e-4);
+
+    let ray = Ray::new(Vec3::new(8.0, 1.5, -0.5), Vec3::new(-1.0, 0.0, 0.0));
+    let hit = instance.hit(&ray, 0.001, f32::MAX).unwrap();
+    assert!((hit.t - 5.0).abs() < 1e-4);
+    assert_close(hit.point, Vec3::new(3.0, 1.5, -0.5));
+    assert_close(hit.normal, Vec3::new(1.0, 0.0, 0.0));
+    assert!((hit.u - 0.25).abs() < 1e-4);
+    assert!((hit.v - 0.75).abs() < 1e-4);
+
+    // The object itself is untouched.
+    let down = Ray::new(Vec3::new(0.25, 0.75, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(quad.hit(&down, 0.001, f32::MAX).is_some());
+}
+
+#[test]
+fn normals_under_non_uniform_scale() {
+    // A tilted plane keeps its normal perpendicular to it when stretched along X, where
+    // transforming the normal like a vector would tilt it the wrong way.
+    let stretch = Transform::scale(Vec3::new(2.0, 1.0, 1.0));
+    let tangent = stretch.vector(Vec3::new(1.0, -1.0, 0.0));
+    let normal = stretch.normal(Vec3::new(1.0, 1.0, 0.0));
+    assert!(raytracer::dot(&tangent, &normal).abs() < 1e-6);
+    assert_close(
+        Vec3::unit_from(normal),
+        Vec3::unit_from(Vec3::new(0.5, 1.0, 0.0)),
+    );
+
+    // A unit sphere stretched into the ellipsoid x^2 / 4 + y^2 + z^2 = 1, whose normal at
+    // (x, y, z) is along (x / 4, y, z).
+    let sphere = Arc::new(Sphere::new(Vec3::default(), 1.0, material()));
+    let ellipsoid = Transformed::new(sphere, stretch);
+    let y = 0.5f32.sqrt();
+    let ray = Ray::new(Vec3::new(5.0, y, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+    let hit = ellipsoid.hit(&ray, 0.001, f32::MAX).unwrap();
+    let x = 2.0 * y;
+    assert_close(hit.point, Vec3::new(x, y, 0.0));
+    assert_close(hit.normal, Vec3::unit_from(Vec3::new(x / 4.0, y, 0.0)));
+}
+
+#[test]
+fn animated_instance_follows_ray_time() {
+    // Slides one unit along X, then turns a quarter around Z about the origin.
+    let animation = AnimatedTransform::new(vec![
+        Keyframe::at(0.0),
+        Keyframe::at(1.0).with_translation(Vec3::new(1.0, 0.0, 0.0)),
+        Keyframe::at(2.0)
+            .with_translation(Vec3::new(1.0, 0.0, 0.0))
+            .with_rotation(Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0)),
+    ]);
+    let instance = Animated::new(unit_quad(), animation);
+
+    let down = |x: f32, y: f32, time: f32| {
+        let ray = Ray::with_time(Vec3::new(x, y, 5.0), Vec3::new(0.0, 0.0, -1.0), time);
+        instance.hit(&ray, 0.001, f32::MAX)
+    };
+    assert!(down(0.25, 0.5, 0.0).is_some());
+    assert!(down(1.25, 0.5, 0.0).is_none());
+    assert!(down(1.25, 0.5, 1.0).is_some());
+    assert!(down(0.25, 0.5, 1.0).is_none());
+    let turned = down(0.75, 0.75, 2.0).unwrap();
+    assert!((turned.u - 0.75).abs() < 1e-4);
+    assert!((turned.v - 0.25).abs() < 1e-4);
+
+    // The quad slides from x = 0 to 2, and its far corner swings up to y = sqrt(2) as it turns.
+    let bounds = instance.bounding_box(0.0, 2.0).unwrap();
+    assert!(bounds.min.x <= 0.0 && bounds.max.x >= 2.0);
+    assert!(bounds.min.y <= 0.0 && bounds.max.y >= 2f32.sqrt());
+    let still = instance.bounding_box(-1.0, 0.0).unwrap();
+    assert_close(still.min, Vec3::new(0.0, 0.0, 0.0));
+    assert_close(still.max, Vec3::new(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn slerp_goes_the_short_way_round() {
+    let z = Vec3::new(0.0, 0.0, 1.0);
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let turned = |q: Quaternion| q.to_transform().vector(x);
+
+    // From 10 degrees to 350 degrees is 20 degrees through 0, not 340 through 180.
+    let from = Quaternion::from_axis_angle(z, 10.0);
+    let to = Quaternion::from_axis_angle(z, 350.0);
+    assert_close(turned(from.slerp(&to, 0.5)), x);
+    let quarter = 5f32.to_radians();
+    assert_close(
+        turned(from.slerp(&to, 0.25)),
+        Vec3::new(quarter.cos(), quarter.sin(), 0.0),
+    );
+
+    // Turning at constant speed, short of half a turn.
+    let to = Quaternion::from_axis_angle(z, 170.0);
+    let angle = 90f32.to_radians();
+    assert_close(
+        turned(from.slerp(&to, 0.5)),
+        Vec3::new(angle.cos(), angle.sin(), 0.0),
+    );
+
+    // A quaternion and its negation are the same rotation, so there is nothing to turn.
+    let negated = Quaternion {
+        w: -from.w,
+        v: -from.v,
+    };
+    for &t in [0.0, 0.3, 1.0].iter() {
+        assert_close(turned(from.slerp(&negated, t)), turned(from));
+    }
+}